@@ -0,0 +1,28 @@
+//! Records which commit Root was built from and when, for the `/version` route.
+
+use std::process::Command;
+
+fn main() {
+    // Docker builds don't copy `.git`, so the commit can be passed in as `GIT_COMMIT` instead.
+    let commit = std::env::var("GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| {
+            let output = Command::new("git")
+                .args(["rev-parse", "--short", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=ROOT_GIT_COMMIT={}", commit);
+    println!(
+        "cargo:rustc-env=ROOT_BUILD_TIME={}",
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    );
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
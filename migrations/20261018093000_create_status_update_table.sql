@@ -0,0 +1,11 @@
+-- Individual status updates, one per member per day. Streaks are derived from these.
+CREATE TABLE StatusUpdate (
+        update_id SERIAL PRIMARY KEY,
+        member_id INT NOT NULL REFERENCES Member(member_id) ON DELETE CASCADE,
+        date DATE NOT NULL,
+        source_message_id VARCHAR(255),
+        content TEXT NOT NULL,
+        posted_at TIMESTAMP NOT NULL,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE (member_id, date)
+);
@@ -0,0 +1,8 @@
+-- Missed days used to be stored as a negative current_streak.
+ALTER TABLE StatusUpdateStreak ADD COLUMN missed_streak INT NOT NULL DEFAULT 0;
+
+UPDATE StatusUpdateStreak
+SET missed_streak = -current_streak, current_streak = 0
+WHERE current_streak < 0;
+
+ALTER TABLE StatusUpdateStreak ADD CONSTRAINT current_streak_non_negative CHECK (current_streak >= 0);
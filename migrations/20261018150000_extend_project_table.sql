@@ -0,0 +1,11 @@
+-- Adds project details and lifecycle status
+CREATE TYPE project_status AS ENUM ('idea', 'active', 'paused', 'completed');
+
+ALTER TABLE Project
+        ADD COLUMN description TEXT,
+        ADD COLUMN repository_url TEXT,
+        ADD COLUMN status project_status NOT NULL DEFAULT 'idea',
+        ADD COLUMN start_date DATE,
+        ADD COLUMN end_date DATE,
+        ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}',
+        ADD CONSTRAINT project_dates CHECK (end_date IS NULL OR start_date IS NULL OR end_date >= start_date);
@@ -0,0 +1,14 @@
+-- Lets several members work on a project, each with a role
+CREATE TYPE project_role AS ENUM ('lead', 'contributor', 'mentor');
+
+CREATE TABLE ProjectMember (
+        project_id INT NOT NULL REFERENCES Project(project_id) ON DELETE CASCADE,
+        member_id INT NOT NULL REFERENCES Member(member_id) ON DELETE CASCADE,
+        role project_role NOT NULL DEFAULT 'contributor',
+        joined_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (project_id, member_id)
+);
+
+-- Whoever created an existing project leads it
+INSERT INTO ProjectMember (project_id, member_id, role)
+SELECT project_id, member_id, 'lead' FROM Project;
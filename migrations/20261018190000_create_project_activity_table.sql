@@ -0,0 +1,10 @@
+-- Daily snapshots of repository activity, imported from the project's forge
+CREATE TABLE ProjectActivity (
+        project_id INT NOT NULL REFERENCES Project(project_id) ON DELETE CASCADE,
+        date DATE NOT NULL,
+        commits INT NOT NULL DEFAULT 0,
+        pull_requests INT NOT NULL DEFAULT 0,
+        issues INT NOT NULL DEFAULT 0,
+        imported_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (project_id, date)
+);
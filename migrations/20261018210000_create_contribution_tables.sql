@@ -0,0 +1,34 @@
+-- Receiving contributions through forge webhooks
+
+-- Links members to their accounts on forges such as GitHub
+CREATE TABLE ForgeAccount (
+        member_id INT NOT NULL REFERENCES Member(member_id) ON DELETE CASCADE,
+        host VARCHAR(255) NOT NULL,
+        username VARCHAR(255) NOT NULL,
+        PRIMARY KEY (member_id, host),
+        UNIQUE (host, username)
+);
+
+CREATE TABLE ForgeWebhook (
+        hook_id SERIAL PRIMARY KEY,
+        project_id INT REFERENCES Project(project_id) ON DELETE CASCADE,
+        secret VARCHAR(255) NOT NULL,
+        counts_as_status_update BOOLEAN NOT NULL DEFAULT FALSE,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TYPE contribution_kind AS ENUM ('commit', 'pull_request_opened', 'pull_request_merged');
+
+CREATE TABLE Contribution (
+        contribution_id SERIAL PRIMARY KEY,
+        member_id INT NOT NULL REFERENCES Member(member_id) ON DELETE CASCADE,
+        project_id INT REFERENCES Project(project_id) ON DELETE SET NULL,
+        kind contribution_kind NOT NULL,
+        repository VARCHAR(255) NOT NULL,
+        reference VARCHAR(255) NOT NULL, -- Commit SHA or pull request number
+        title TEXT NOT NULL,
+        url TEXT,
+        contributed_at TIMESTAMP NOT NULL,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE (kind, repository, reference) -- Redelivered webhooks shouldn't count twice
+);
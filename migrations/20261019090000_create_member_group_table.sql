@@ -0,0 +1,19 @@
+-- Gives Member.group_id a table to point to
+CREATE TABLE MemberGroup (
+        group_id SERIAL PRIMARY KEY,
+        name VARCHAR(255) NOT NULL UNIQUE,
+        description TEXT,
+        mentor_id INT REFERENCES Member(member_id) ON DELETE SET NULL,
+        meeting_slot VARCHAR(255), -- Free form, e.g. "Saturday 16:00"
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Every group already in use gets a placeholder row that can be renamed later
+INSERT INTO MemberGroup (group_id, name)
+SELECT DISTINCT group_id, 'Group ' || group_id FROM Member;
+
+SELECT setval(pg_get_serial_sequence('MemberGroup', 'group_id'), COALESCE(MAX(group_id), 0) + 1, false)
+FROM MemberGroup;
+
+ALTER TABLE Member
+        ADD CONSTRAINT fkey_member_group FOREIGN KEY (group_id) REFERENCES MemberGroup(group_id);
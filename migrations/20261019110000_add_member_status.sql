@@ -0,0 +1,22 @@
+-- Tracks whether members are still around
+CREATE TYPE member_status AS ENUM ('active', 'on_break', 'inactive', 'alumni');
+
+ALTER TABLE Member
+        ADD COLUMN status member_status NOT NULL DEFAULT 'active',
+        ADD COLUMN status_since DATE NOT NULL DEFAULT CURRENT_DATE;
+
+-- Changes can be scheduled ahead of time, the daily task applies them once effective_from arrives
+CREATE TABLE MemberStatusChange (
+        change_id SERIAL PRIMARY KEY,
+        member_id INT NOT NULL REFERENCES Member(member_id) ON DELETE CASCADE,
+        status member_status NOT NULL,
+        effective_from DATE NOT NULL,
+        reason TEXT,
+        applied BOOLEAN NOT NULL DEFAULT FALSE,
+        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Existing members have been active since they joined
+UPDATE Member SET status_since = COALESCE(created_at::date, CURRENT_DATE);
+INSERT INTO MemberStatusChange (member_id, status, effective_from, reason, applied)
+SELECT member_id, 'active', status_since, 'Joined', TRUE FROM Member;
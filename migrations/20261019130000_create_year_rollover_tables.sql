@@ -0,0 +1,25 @@
+-- Every academic year, active members move up a year and final years become alumni
+CREATE TABLE YearRollover (
+        rollover_id SERIAL PRIMARY KEY,
+        academic_year INT NOT NULL,
+        scheduled BOOLEAN NOT NULL DEFAULT FALSE,
+        promoted INT NOT NULL,
+        graduated INT NOT NULL,
+        performed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        undone_at TIMESTAMP
+);
+
+-- An academic year can only be rolled over again after undoing the earlier rollover
+CREATE UNIQUE INDEX year_rollover_academic_year ON YearRollover (academic_year) WHERE undone_at IS NULL;
+
+-- What each rollover changed, so it can be undone
+CREATE TABLE YearRolloverEntry (
+        rollover_id INT NOT NULL REFERENCES YearRollover(rollover_id) ON DELETE CASCADE,
+        member_id INT NOT NULL REFERENCES Member(member_id) ON DELETE CASCADE,
+        previous_year INT NOT NULL,
+        new_year INT NOT NULL,
+        graduated BOOLEAN NOT NULL,
+        previous_status_since DATE NOT NULL,
+        status_change_id INT REFERENCES MemberStatusChange(change_id) ON DELETE SET NULL,
+        PRIMARY KEY (rollover_id, member_id)
+);
@@ -0,0 +1,17 @@
+-- Optional details members can show on their profile on Home
+CREATE TABLE MemberProfile (
+        member_id INT PRIMARY KEY REFERENCES Member(member_id) ON DELETE CASCADE,
+        bio TEXT,
+        pronouns VARCHAR(32),
+        avatar_url VARCHAR(255),
+        website_url VARCHAR(255),
+        github_handle VARCHAR(100),
+        gitlab_handle VARCHAR(100),
+        linkedin_handle VARCHAR(100),
+        skills TEXT[] NOT NULL DEFAULT '{}',
+        interests TEXT[] NOT NULL DEFAULT '{}',
+        is_public BOOLEAN NOT NULL DEFAULT TRUE,
+        show_socials BOOLEAN NOT NULL DEFAULT TRUE,
+        show_email BOOLEAN NOT NULL DEFAULT FALSE,
+        updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,37 @@
+-- Monthly attendance reports, kept as snapshots so they don't change when old data does
+CREATE TABLE MonthlyReport (
+        report_id SERIAL PRIMARY KEY,
+        year INT NOT NULL,
+        month INT NOT NULL CHECK (month BETWEEN 1 AND 12),
+        working_days INT NOT NULL,
+        late_after TIME NOT NULL,
+        generated_at TIMESTAMP NOT NULL,
+        UNIQUE (year, month)
+);
+
+CREATE TABLE MemberMonthlyReport (
+        report_id INT NOT NULL REFERENCES MonthlyReport(report_id) ON DELETE CASCADE,
+        member_id INT NOT NULL REFERENCES Member(member_id) ON DELETE CASCADE,
+        group_id INT NOT NULL,
+        working_days INT NOT NULL,
+        days_present INT NOT NULL,
+        attendance_percentage FLOAT8 NOT NULL,
+        average_hours FLOAT8,
+        late_arrivals INT NOT NULL,
+        current_streak INT NOT NULL,
+        max_streak INT NOT NULL,
+        missed_streak INT NOT NULL,
+        PRIMARY KEY (report_id, member_id)
+);
+
+CREATE TABLE GroupMonthlyReport (
+        report_id INT NOT NULL REFERENCES MonthlyReport(report_id) ON DELETE CASCADE,
+        group_id INT NOT NULL REFERENCES MemberGroup(group_id) ON DELETE CASCADE,
+        members INT NOT NULL,
+        days_present INT NOT NULL,
+        attendance_percentage FLOAT8 NOT NULL,
+        average_hours FLOAT8,
+        late_arrivals INT NOT NULL,
+        members_on_streak INT NOT NULL,
+        PRIMARY KEY (report_id, group_id)
+);
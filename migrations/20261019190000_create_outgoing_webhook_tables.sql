@@ -0,0 +1,52 @@
+-- Outgoing webhooks, notifying other services of changes in Root
+CREATE TYPE webhook_event_type AS ENUM (
+        'ping',
+        'member.created',
+        'member.updated',
+        'attendance.marked',
+        'streak.broken',
+        'project.updated'
+);
+
+CREATE TYPE webhook_delivery_status AS ENUM ('pending', 'delivered', 'failed');
+
+CREATE TABLE WebhookSubscription (
+        subscription_id SERIAL PRIMARY KEY,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        -- Empty to receive every event
+        events webhook_event_type[] NOT NULL DEFAULT '{}',
+        active BOOLEAN NOT NULL DEFAULT TRUE,
+        created_at TIMESTAMP NOT NULL
+);
+
+-- The outbox. Events are inserted in the same transaction as the change they describe
+CREATE TABLE WebhookEvent (
+        event_id SERIAL PRIMARY KEY,
+        event_type webhook_event_type NOT NULL,
+        payload JSONB NOT NULL,
+        created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE WebhookDelivery (
+        delivery_id SERIAL PRIMARY KEY,
+        event_id INT NOT NULL REFERENCES WebhookEvent(event_id) ON DELETE CASCADE,
+        subscription_id INT NOT NULL REFERENCES WebhookSubscription(subscription_id) ON DELETE CASCADE,
+        status webhook_delivery_status NOT NULL DEFAULT 'pending',
+        attempts INT NOT NULL DEFAULT 0,
+        next_attempt_at TIMESTAMP NOT NULL,
+        delivered_at TIMESTAMP,
+        UNIQUE (event_id, subscription_id)
+);
+
+CREATE INDEX webhook_delivery_due ON WebhookDelivery (next_attempt_at) WHERE status = 'pending';
+
+-- The delivery log, one row per request sent
+CREATE TABLE WebhookDeliveryAttempt (
+        attempt_id SERIAL PRIMARY KEY,
+        delivery_id INT NOT NULL REFERENCES WebhookDelivery(delivery_id) ON DELETE CASCADE,
+        attempted_at TIMESTAMP NOT NULL,
+        status_code INT,
+        error TEXT,
+        duration_ms INT NOT NULL
+);
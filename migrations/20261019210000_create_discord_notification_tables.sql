@@ -0,0 +1,22 @@
+-- Messages posted to Discord channels through their webhooks
+CREATE TYPE notification_kind AS ENUM (
+        'daily_absentees',
+        'late_check_ins',
+        'streak_milestones',
+        'streaks_broken'
+);
+
+CREATE TABLE DiscordChannel (
+        channel_id SERIAL PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        webhook_url TEXT NOT NULL,
+        created_at TIMESTAMP NOT NULL
+);
+
+-- Which notifications go to which channel, each with an optional custom template
+CREATE TABLE DiscordRoute (
+        channel_id INT NOT NULL REFERENCES DiscordChannel(channel_id) ON DELETE CASCADE,
+        kind notification_kind NOT NULL,
+        template TEXT,
+        PRIMARY KEY (channel_id, kind)
+);
@@ -0,0 +1,5 @@
+-- Members who don't want weekly group digests by email
+CREATE TABLE DigestOptOut (
+        member_id INT PRIMARY KEY REFERENCES Member(member_id) ON DELETE CASCADE,
+        opted_out_at TIMESTAMP NOT NULL
+);
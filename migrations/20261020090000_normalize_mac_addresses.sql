@@ -0,0 +1,16 @@
+-- Stores MAC addresses added before validation as AA:BB:CC:DD:EE:FF, like new ones,
+-- so that the uniqueness constraint also catches differently written duplicates.
+-- Addresses that aren't 12 hex digits, or would collide with another member's, are left for an admin to fix.
+WITH digits AS (
+        SELECT member_id, upper(regexp_replace(mac_address, '[:.-]', '', 'g')) AS digits
+        FROM Member
+), normalized AS (
+        SELECT member_id, regexp_replace(digits, '^(..)(..)(..)(..)(..)(..)$', '\1:\2:\3:\4:\5:\6') AS mac_address
+        FROM digits
+        WHERE digits ~ '^[0-9A-F]{12}$'
+)
+UPDATE Member m SET mac_address = n.mac_address
+FROM normalized n
+WHERE m.member_id = n.member_id
+        AND m.mac_address <> n.mac_address
+        AND (SELECT COUNT(*) FROM normalized o WHERE o.mac_address = n.mac_address) = 1;
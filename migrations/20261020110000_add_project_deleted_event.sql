@@ -0,0 +1,2 @@
+-- Lets webhook subscribers hear about deleted projects
+ALTER TYPE webhook_event_type ADD VALUE 'project.deleted';
@@ -0,0 +1,3 @@
+-- Members can claim any username, so their links only count once an admin approves them.
+-- Existing links were never checked and have to be approved too
+ALTER TABLE ForgeAccount ADD COLUMN approved BOOLEAN NOT NULL DEFAULT FALSE;
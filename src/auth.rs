@@ -0,0 +1,224 @@
+use async_graphql::{Context, Error, ErrorExtensions, Result};
+use axum::http::{header, HeaderMap};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::metrics::{HmacCheck, Metrics};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Who sent a GraphQL request. Added to every request's data by the GraphQL handler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Viewer {
+    Anonymous,
+    Member(i32),
+    /// Admin tools, authenticated with `ROOT_ADMIN_TOKEN`.
+    Admin,
+}
+
+/// Checks member tokens sent as `Authorization: Bearer <token>`. Tokens are issued by
+/// trusted frontends such as Home once a member signs in, and look like
+/// `<member_id>.<expires_at>.<signature>`, where `expires_at` is a unix timestamp and
+/// `signature` the hex HMAC-SHA256 of `<member_id>.<expires_at>` keyed with
+/// `ROOT_MEMBER_TOKEN_SECRET`. Admin tools send `ROOT_ADMIN_TOKEN` instead.
+#[derive(Clone)]
+pub struct Authenticator {
+    secret: String,
+    admin_token: Option<String>,
+    metrics: Metrics,
+}
+
+impl Authenticator {
+    pub fn new(secret: String, admin_token: Option<String>, metrics: Metrics) -> Self {
+        Self {
+            secret,
+            admin_token,
+            metrics,
+        }
+    }
+
+    /// Missing, malformed and expired tokens all make the request anonymous.
+    pub fn viewer(&self, headers: &HeaderMap) -> Viewer {
+        let Some(token) = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+        else {
+            return Viewer::Anonymous;
+        };
+
+        let is_admin = self
+            .admin_token
+            .as_deref()
+            .is_some_and(|admin_token| constant_time_eq(admin_token.as_bytes(), token.as_bytes()));
+        if is_admin {
+            return Viewer::Admin;
+        }
+
+        self.verify_member_token(token, chrono::Utc::now().timestamp())
+            .map_or(Viewer::Anonymous, Viewer::Member)
+    }
+
+    fn verify_member_token(&self, token: &str, now: i64) -> Option<i32> {
+        let (payload, signature) = token.rsplit_once('.')?;
+        let (member_id, expires_at) = payload.split_once('.')?;
+        let member_id: i32 = member_id.parse().ok()?;
+        let expires_at: i64 = expires_at.parse().ok()?;
+
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(payload.as_bytes());
+        let verified =
+            hex::decode(signature).is_ok_and(|signature| mac.verify_slice(&signature).is_ok());
+        if !verified {
+            self.metrics.record_hmac_failure(HmacCheck::MemberToken);
+            return None;
+        }
+
+        (now < expires_at).then_some(member_id)
+    }
+}
+
+/// Compares without returning early, so the time taken doesn't reveal how much of a token matched.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The member sending the request, or an `UNAUTHENTICATED` error for anonymous requests.
+pub fn signed_in_member(ctx: &Context<'_>) -> Result<i32, Error> {
+    match ctx.data::<Viewer>().expect("Viewer must be in context.") {
+        Viewer::Member(member_id) => Ok(*member_id),
+        Viewer::Anonymous | Viewer::Admin => {
+            Err(Error::new("Sign in required")
+                .extend_with(|_, ext| ext.set("code", "UNAUTHENTICATED")))
+        }
+    }
+}
+
+/// Fails with a `FORBIDDEN` error unless the request comes from an admin tool.
+pub fn require_admin(ctx: &Context<'_>) -> Result<()> {
+    match ctx.data::<Viewer>().expect("Viewer must be in context.") {
+        Viewer::Admin => Ok(()),
+        _ => Err(Error::new("Only admins can do this")
+            .extend_with(|_, ext| ext.set("code", "FORBIDDEN"))),
+    }
+}
+
+/// Fails unless the request comes from `member_id` themselves or an admin tool, with
+/// `UNAUTHENTICATED` for anonymous requests and `FORBIDDEN` for other members.
+pub fn require_member_or_admin(ctx: &Context<'_>, member_id: i32) -> Result<()> {
+    match ctx.data::<Viewer>().expect("Viewer must be in context.") {
+        Viewer::Admin => Ok(()),
+        Viewer::Member(viewer) if *viewer == member_id => Ok(()),
+        Viewer::Member(_) => Err(Error::new("Only admins can do this for other members")
+            .extend_with(|_, ext| ext.set("code", "FORBIDDEN"))),
+        Viewer::Anonymous => {
+            Err(Error::new("Sign in required")
+                .extend_with(|_, ext| ext.set("code", "UNAUTHENTICATED")))
+        }
+    }
+}
+
+/// Who can see a field with personal data. Fields without a visibility are public.
+#[derive(Clone, Copy)]
+pub enum Visibility {
+    /// Any signed in member, and admins.
+    MemberOnly,
+    AdminOnly,
+}
+
+impl Visibility {
+    /// Returns `value`, a field of `owner`'s data, if the viewer may see it. Members can
+    /// always see their own data. Otherwise the field is reported as `FORBIDDEN` and
+    /// resolves to `null`, leaving the rest of the object and any list it's in intact,
+    /// which a guard failing would not.
+    pub fn reveal<T>(self, ctx: &Context<'_>, owner: i32, value: T) -> Option<T> {
+        let viewer = ctx.data::<Viewer>().expect("Viewer must be in context.");
+
+        let allowed = match (self, viewer) {
+            (_, Viewer::Admin) => true,
+            (_, Viewer::Member(member_id)) if *member_id == owner => true,
+            (Visibility::MemberOnly, Viewer::Member(_)) => true,
+            _ => false,
+        };
+
+        if allowed {
+            return Some(value);
+        }
+        let error = Error::new("Not allowed to see this field")
+            .extend_with(|_, ext| ext.set("code", "FORBIDDEN"))
+            .into_server_error(ctx.item.pos);
+        ctx.add_error(ctx.set_error_path(error));
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn authenticator() -> Authenticator {
+        Authenticator::new(
+            "membersecret".to_string(),
+            Some("admintoken".to_string()),
+            Metrics::new(),
+        )
+    }
+
+    fn token(secret: &str, member_id: i32, expires_at: i64) -> String {
+        let payload = format!("{}.{}", member_id, expires_at);
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(payload.as_bytes());
+        format!("{}.{}", payload, hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn accepts_valid_member_token() {
+        let token = token("membersecret", 7, NOW + 60);
+        assert_eq!(authenticator().verify_member_token(&token, NOW), Some(7));
+    }
+
+    #[test]
+    fn rejects_expired_member_token() {
+        let token = token("membersecret", 7, NOW);
+        assert_eq!(authenticator().verify_member_token(&token, NOW), None);
+    }
+
+    #[test]
+    fn rejects_token_signed_with_another_secret() {
+        let token = token("othersecret", 7, NOW + 60);
+        assert_eq!(authenticator().verify_member_token(&token, NOW), None);
+    }
+
+    #[test]
+    fn rejects_tampered_member_token() {
+        let token = token("membersecret", 7, NOW + 60).replacen('7', "8", 1);
+        assert_eq!(authenticator().verify_member_token(&token, NOW), None);
+    }
+
+    #[test]
+    fn rejects_malformed_member_tokens() {
+        let authenticator = authenticator();
+        for token in ["", "7", "7.123", "x.123.abcd", "7.y.abcd", "7.123.nothex"] {
+            assert_eq!(
+                authenticator.verify_member_token(token, NOW),
+                None,
+                "{}",
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn recognises_admin_token() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer admintoken".parse().unwrap());
+        assert_eq!(authenticator().viewer(&headers), Viewer::Admin);
+
+        headers.insert(header::AUTHORIZATION, "Bearer admintoken2".parse().unwrap());
+        assert_eq!(authenticator().viewer(&headers), Viewer::Anonymous);
+    }
+}
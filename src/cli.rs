@@ -0,0 +1,55 @@
+use chrono::Local;
+use sqlx::PgPool;
+
+use crate::{config::timezone, lifecycle::import::import_members, validation::ValidationRules};
+
+const USAGE: &str = "Usage: root import-members <file.csv> [--dry-run]";
+
+/// Runs a one-off command such as `root import-members members.csv` instead of the server.
+pub async fn run(args: &[String], pool: &PgPool, rules: &ValidationRules) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("import-members") => {
+            let dry_run = args.iter().any(|arg| arg == "--dry-run");
+            let path = args[1..]
+                .iter()
+                .find(|arg| !arg.starts_with("--"))
+                .ok_or(USAGE)?;
+            import_members_from_file(path, dry_run, pool, rules).await
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+async fn import_members_from_file(
+    path: &str,
+    dry_run: bool,
+    pool: &PgPool,
+    rules: &ValidationRules,
+) -> Result<(), String> {
+    let csv = std::fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path, e))?;
+
+    let today = Local::now().with_timezone(&timezone()).date_naive();
+    let report = import_members(&csv, rules, today, dry_run, pool)
+        .await
+        .map_err(|e| format!("Import failed: {}", e))?;
+
+    if !report.errors.is_empty() {
+        for error in &report.errors {
+            match &error.field {
+                Some(field) => eprintln!("Row {}, {}: {}", error.row, field, error.message),
+                None => eprintln!("Row {}: {}", error.row, error.message),
+            }
+        }
+        return Err(format!(
+            "Nothing was imported, {} errors found",
+            report.errors.len()
+        ));
+    }
+
+    if dry_run {
+        println!("{} members would be imported", report.imported);
+    } else {
+        println!("Imported {} members", report.imported);
+    }
+    Ok(())
+}
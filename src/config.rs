@@ -0,0 +1,1018 @@
+//! Settings are read from a TOML file, `root.toml` by default, and can be overridden with
+//! environment variables. See `root.example.toml` for every setting and its variable.
+
+use std::{fmt, sync::OnceLock, time::Duration};
+
+use axum::http::HeaderValue;
+use chrono::{NaiveTime, Weekday};
+use chrono_tz::Tz;
+use config::{File, FileFormat};
+use lettre::message::Mailbox;
+use serde::Deserialize;
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    email::{SmtpSecurity, SmtpSettings},
+    events::delivery::DeliveryRules,
+    graphql::limits::QueryLimits,
+    lifecycle::rollover::RolloverRules,
+    project_activity::github,
+    rate_limit::RateLimitConfig,
+    reports::ReportRules,
+    streaks::StreakRules,
+    validation::{ValidationRules, DEFAULT_ROLL_NO_PATTERN},
+};
+
+/// Used when `ROOT_CONFIG` doesn't point to another file. It's fine for it not to exist.
+const DEFAULT_CONFIG_FILE: &str = "root.toml";
+
+const DEFAULT_TIMEZONE: Tz = chrono_tz::Asia::Kolkata;
+
+static TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+/// The timezone dates and times are stored and scheduled in, `Asia/Kolkata` unless configured.
+pub fn timezone() -> Tz {
+    *TIMEZONE.get().unwrap_or(&DEFAULT_TIMEZONE)
+}
+
+/// Whether a setting's variable holds a single value or a comma separated list.
+enum Kind {
+    Value,
+    List,
+}
+
+/// Environment variables overriding settings from the file.
+const ENV_OVERRIDES: [(&str, &str, Kind); 44] = [
+    ("ROOT_ENV", "server.env", Kind::Value),
+    ("ROOT_HOST", "server.host", Kind::Value),
+    ("ROOT_PORT", "server.port", Kind::Value),
+    ("ROOT_SECRET", "server.secret", Kind::Value),
+    (
+        "ROOT_MEMBER_TOKEN_SECRET",
+        "server.member_token_secret",
+        Kind::Value,
+    ),
+    (
+        "ROOT_UNSUBSCRIBE_SECRET",
+        "server.unsubscribe_secret",
+        Kind::Value,
+    ),
+    ("ROOT_ADMIN_TOKEN", "server.admin_token", Kind::Value),
+    ("ROOT_PUBLIC_URL", "server.public_url", Kind::Value),
+    ("ROOT_METRICS_TOKEN", "server.metrics_token", Kind::Value),
+    (
+        "ROOT_MAX_QUERY_DEPTH",
+        "server.max_query_depth",
+        Kind::Value,
+    ),
+    (
+        "ROOT_MAX_QUERY_COMPLEXITY",
+        "server.max_query_complexity",
+        Kind::Value,
+    ),
+    (
+        "ROOT_RATE_LIMIT_BURST",
+        "server.rate_limit_burst",
+        Kind::Value,
+    ),
+    (
+        "ROOT_RATE_LIMIT_PER_SECOND",
+        "server.rate_limit_per_second",
+        Kind::Value,
+    ),
+    (
+        "ROOT_TRUST_FORWARDED_FOR",
+        "server.trust_forwarded_for",
+        Kind::Value,
+    ),
+    ("ROOT_CORS_ORIGINS", "server.cors_origins", Kind::List),
+    (
+        "ROOT_SHUTDOWN_TIMEOUT_SECONDS",
+        "server.shutdown_timeout_seconds",
+        Kind::Value,
+    ),
+    ("DATABASE_URL", "database.url", Kind::Value),
+    (
+        "ROOT_DB_MIN_CONNECTIONS",
+        "database.min_connections",
+        Kind::Value,
+    ),
+    (
+        "ROOT_DB_MAX_CONNECTIONS",
+        "database.max_connections",
+        Kind::Value,
+    ),
+    (
+        "ROOT_DB_ACQUIRE_TIMEOUT_SECONDS",
+        "database.acquire_timeout_seconds",
+        Kind::Value,
+    ),
+    ("ROOT_TIMEZONE", "timezone", Kind::Value),
+    ("ROOT_DAILY_TASK_AT", "schedule.daily_task_at", Kind::Value),
+    ("ROOT_DIGEST_DAY", "schedule.digest_day", Kind::Value),
+    ("ROOT_ROLLOVER_DATE", "schedule.rollover_date", Kind::Value),
+    (
+        "ROOT_ROLLOVER_UNDO_DAYS",
+        "schedule.rollover_undo_days",
+        Kind::Value,
+    ),
+    ("ROOT_LOG_LEVEL", "logging.level", Kind::Value),
+    ("ROOT_LOG_FILE", "logging.file", Kind::Value),
+    (
+        "ROOT_ROLL_NO_PATTERN",
+        "members.roll_no_pattern",
+        Kind::Value,
+    ),
+    (
+        "ROOT_STREAK_DEADLINE",
+        "members.streak_deadline",
+        Kind::Value,
+    ),
+    (
+        "ROOT_STREAK_EXCLUDED_DAYS",
+        "members.streak_excluded_days",
+        Kind::List,
+    ),
+    ("ROOT_LATE_AFTER", "members.late_after", Kind::Value),
+    (
+        "ROOT_GITHUB_API_URL",
+        "integrations.github.api_url",
+        Kind::Value,
+    ),
+    (
+        "ROOT_GITHUB_TOKEN",
+        "integrations.github.token",
+        Kind::Value,
+    ),
+    (
+        "ROOT_STREAK_MILESTONES",
+        "integrations.discord.streak_milestones",
+        Kind::List,
+    ),
+    (
+        "ROOT_WEBHOOK_MAX_ATTEMPTS",
+        "integrations.webhooks.max_attempts",
+        Kind::Value,
+    ),
+    (
+        "ROOT_WEBHOOK_RETRY_SECONDS",
+        "integrations.webhooks.retry_seconds",
+        Kind::Value,
+    ),
+    (
+        "ROOT_WEBHOOK_POLL_SECONDS",
+        "integrations.webhooks.poll_seconds",
+        Kind::Value,
+    ),
+    (
+        "ROOT_WEBHOOK_RETENTION_DAYS",
+        "integrations.webhooks.retention_days",
+        Kind::Value,
+    ),
+    ("ROOT_SMTP_HOST", "integrations.smtp.host", Kind::Value),
+    ("ROOT_SMTP_PORT", "integrations.smtp.port", Kind::Value),
+    (
+        "ROOT_SMTP_SECURITY",
+        "integrations.smtp.security",
+        Kind::Value,
+    ),
+    (
+        "ROOT_SMTP_USERNAME",
+        "integrations.smtp.username",
+        Kind::Value,
+    ),
+    (
+        "ROOT_SMTP_PASSWORD",
+        "integrations.smtp.password",
+        Kind::Value,
+    ),
+    ("ROOT_MAIL_FROM", "integrations.smtp.from", Kind::Value),
+];
+
+/// Every problem found while loading settings, so they can all be fixed at once.
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration:")?;
+        for problem in &self.0 {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<config::ConfigError> for ConfigError {
+    fn from(e: config::ConfigError) -> Self {
+        ConfigError(vec![e.to_string()])
+    }
+}
+
+/// The settings as written in the file, before they are checked.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Settings {
+    #[serde(default = "default_timezone")]
+    timezone: String,
+    #[serde(default)]
+    server: ServerSettings,
+    #[serde(default)]
+    database: DatabaseSettings,
+    #[serde(default)]
+    schedule: ScheduleSettings,
+    #[serde(default)]
+    logging: LoggingSettings,
+    #[serde(default)]
+    members: MemberSettings,
+    #[serde(default)]
+    integrations: IntegrationSettings,
+}
+
+fn default_timezone() -> String {
+    DEFAULT_TIMEZONE.name().to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServerSettings {
+    env: String,
+    host: String,
+    port: u16,
+    secret: Option<String>,
+    member_token_secret: Option<String>,
+    unsubscribe_secret: Option<String>,
+    admin_token: Option<String>,
+    public_url: Option<String>,
+    metrics_token: Option<String>,
+    max_query_depth: usize,
+    max_query_complexity: usize,
+    rate_limit_burst: u32,
+    rate_limit_per_second: f64,
+    trust_forwarded_for: bool,
+    cors_origins: Vec<String>,
+    shutdown_timeout_seconds: u64,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            env: "development".to_string(),
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            secret: None,
+            member_token_secret: None,
+            unsubscribe_secret: None,
+            admin_token: None,
+            public_url: None,
+            metrics_token: None,
+            max_query_depth: 10,
+            max_query_complexity: 250,
+            rate_limit_burst: 30,
+            rate_limit_per_second: 5.0,
+            trust_forwarded_for: false,
+            cors_origins: vec![
+                "http://127.0.0.1:3000".to_string(),
+                "https://home.amfoss.in".to_string(),
+            ],
+            shutdown_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DatabaseSettings {
+    url: Option<String>,
+    min_connections: u32,
+    max_connections: u32,
+    acquire_timeout_seconds: u64,
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            min_connections: 2,
+            max_connections: 3,
+            acquire_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ScheduleSettings {
+    daily_task_at: String,
+    digest_day: String,
+    rollover_date: String,
+    rollover_undo_days: i64,
+}
+
+impl Default for ScheduleSettings {
+    fn default() -> Self {
+        Self {
+            daily_task_at: "00:30".to_string(),
+            digest_day: "Mon".to_string(),
+            rollover_date: "06-01".to_string(),
+            rollover_undo_days: 14,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LoggingSettings {
+    level: Option<String>,
+    file: String,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: None,
+            file: "root.log".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct MemberSettings {
+    roll_no_pattern: String,
+    streak_deadline: String,
+    streak_excluded_days: Vec<String>,
+    late_after: String,
+}
+
+impl Default for MemberSettings {
+    fn default() -> Self {
+        Self {
+            roll_no_pattern: DEFAULT_ROLL_NO_PATTERN.to_string(),
+            streak_deadline: "00:00".to_string(),
+            streak_excluded_days: vec!["Sun".to_string()],
+            late_after: "09:30".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct IntegrationSettings {
+    github: GitHubSettings,
+    discord: DiscordSettings,
+    webhooks: WebhookSettings,
+    smtp: SmtpFileSettings,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GitHubSettings {
+    api_url: String,
+    token: Option<String>,
+}
+
+impl Default for GitHubSettings {
+    fn default() -> Self {
+        Self {
+            api_url: github::DEFAULT_API_URL.to_string(),
+            token: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DiscordSettings {
+    streak_milestones: Vec<String>,
+}
+
+impl Default for DiscordSettings {
+    fn default() -> Self {
+        Self {
+            streak_milestones: ["7", "14", "30", "50", "100"].map(String::from).to_vec(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WebhookSettings {
+    max_attempts: i32,
+    retry_seconds: i64,
+    poll_seconds: u64,
+    retention_days: i64,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            retry_seconds: 60,
+            poll_seconds: 5,
+            retention_days: 30,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SmtpFileSettings {
+    host: Option<String>,
+    port: u16,
+    security: String,
+    username: Option<String>,
+    password: Option<String>,
+    from: String,
+}
+
+impl Default for SmtpFileSettings {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: 587,
+            security: "starttls".to_string(),
+            username: None,
+            password: None,
+            from: "amFOSS Root <root@amfoss.in>".to_string(),
+        }
+    }
+}
+
+pub struct DatabaseConfig {
+    pub url: String,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    pub acquire_timeout: Duration,
+}
+
+pub struct LoggingConfig {
+    /// An `EnvFilter` directive such as `info` or `root=debug,sqlx=warn`.
+    pub level: String,
+    /// `None` if logging to a file is turned off with an empty path.
+    pub file: Option<String>,
+}
+
+/// Checked settings, ready to be used.
+pub struct Config {
+    pub env: String,
+    pub host: String,
+    pub port: u16,
+    /// Verifies attendance marked by Presense.
+    pub secret_key: String,
+    /// Verifies member tokens issued by frontends such as Home.
+    pub member_token_secret: String,
+    pub admin_token: Option<String>,
+    pub public_url: Option<String>,
+    /// Signs unsubscribe links. Set whenever `public_url` is.
+    pub unsubscribe_secret: Option<String>,
+    pub metrics_token: Option<String>,
+    pub query_limits: QueryLimits,
+    pub rate_limit: RateLimitConfig,
+    pub cors_origins: Vec<HeaderValue>,
+    /// How long background tasks get to finish after in-flight requests are drained.
+    pub shutdown_timeout: Duration,
+    pub database: DatabaseConfig,
+    pub timezone: Tz,
+    /// When the daily task runs every day.
+    pub daily_task_at: NaiveTime,
+    pub digest_day: Weekday,
+    pub rollover_rules: RolloverRules,
+    pub logging: LoggingConfig,
+    pub validation_rules: ValidationRules,
+    pub streak_rules: StreakRules,
+    pub report_rules: ReportRules,
+    pub github_api_url: String,
+    pub github_token: Option<String>,
+    pub streak_milestones: Vec<i32>,
+    pub delivery_rules: DeliveryRules,
+    /// `None` unless an SMTP host is set, which disables email.
+    pub smtp: Option<SmtpSettings>,
+    pub mail_from: Mailbox,
+}
+
+impl Config {
+    /// Reads the file named by `ROOT_CONFIG`, or `root.toml` if it exists, then applies
+    /// environment variables, including those in `.env`.
+    pub fn load() -> Result<Self, ConfigError> {
+        let _ = dotenv::dotenv();
+
+        let (path, required) = match std::env::var("ROOT_CONFIG") {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+        let mut builder = config::Config::builder()
+            .add_source(File::new(&path, FileFormat::Toml).required(required));
+
+        for (var, key, kind) in &ENV_OVERRIDES {
+            let Ok(value) = std::env::var(var) else {
+                continue;
+            };
+            builder = match kind {
+                Kind::Value => builder.set_override(*key, value)?,
+                Kind::List => builder.set_override(*key, split_list(&value))?,
+            };
+        }
+
+        let settings: Settings = builder.build()?.try_deserialize().map_err(name_variable)?;
+        Self::check(settings)
+    }
+
+    fn check(settings: Settings) -> Result<Self, ConfigError> {
+        let Settings {
+            timezone,
+            server,
+            database,
+            schedule,
+            logging,
+            members,
+            integrations,
+        } = settings;
+        let mut problems = Problems::default();
+
+        let secret_key = problems.check(
+            required(server.secret, "server.secret", "ROOT_SECRET"),
+            String::new(),
+        );
+        let member_token_secret = problems.check(
+            required(
+                server.member_token_secret,
+                "server.member_token_secret",
+                "ROOT_MEMBER_TOKEN_SECRET",
+            ),
+            String::new(),
+        );
+        let public_url = non_empty(server.public_url);
+        let unsubscribe_secret = non_empty(server.unsubscribe_secret);
+        if public_url.is_some() && unsubscribe_secret.is_none() {
+            problems.add(
+                "server.unsubscribe_secret must be set with server.public_url, to sign unsubscribe links (ROOT_UNSUBSCRIBE_SECRET)"
+                    .to_string(),
+            );
+        }
+        // Services holding one secret mustn't be able to forge what the others sign.
+        let secrets = [
+            ("server.secret", "ROOT_SECRET", Some(&secret_key)),
+            (
+                "server.member_token_secret",
+                "ROOT_MEMBER_TOKEN_SECRET",
+                Some(&member_token_secret),
+            ),
+            (
+                "server.unsubscribe_secret",
+                "ROOT_UNSUBSCRIBE_SECRET",
+                unsubscribe_secret.as_ref(),
+            ),
+        ];
+        for (i, (key, var, secret)) in secrets.iter().enumerate() {
+            for (other_key, other_var, other) in &secrets[i + 1..] {
+                if secret.is_some_and(|secret| !secret.is_empty() && Some(secret) == *other) {
+                    problems.add(format!(
+                        "{} and {} must be different secrets ({}, {})",
+                        key, other_key, var, other_var
+                    ));
+                }
+            }
+        }
+        if server.rate_limit_burst == 0 {
+            problems.add(
+                "server.rate_limit_burst must be at least 1 (ROOT_RATE_LIMIT_BURST)".to_string(),
+            );
+        }
+        // Also rules out NaN, which fails every comparison.
+        if !(server.rate_limit_per_second > 0.0 && server.rate_limit_per_second.is_finite()) {
+            problems.add(format!(
+                "server.rate_limit_per_second ({}) must be above 0 (ROOT_RATE_LIMIT_PER_SECOND)",
+                server.rate_limit_per_second
+            ));
+        }
+        let metrics_token = non_empty(server.metrics_token);
+        if server.env == "production" && metrics_token.is_none() {
+            problems.add(
+                "server.metrics_token must be set in production, so /metrics isn't public (ROOT_METRICS_TOKEN)"
+                    .to_string(),
+            );
+        }
+        let database_url = problems.check(
+            required(database.url, "database.url", "DATABASE_URL"),
+            String::new(),
+        );
+        let timezone = problems.check(
+            timezone.parse::<Tz>().map_err(|_| {
+                format!(
+                    "timezone `{}` must be an IANA timezone such as Asia/Kolkata (ROOT_TIMEZONE)",
+                    timezone
+                )
+            }),
+            DEFAULT_TIMEZONE,
+        );
+
+        let cors_origins = problems.check(
+            server
+                .cors_origins
+                .iter()
+                .map(|origin| parse_origin(origin))
+                .collect(),
+            Vec::new(),
+        );
+        if database.max_connections == 0 || database.min_connections > database.max_connections {
+            problems.add(format!(
+                "database.max_connections ({}) must be at least 1 and at least database.min_connections ({}) (ROOT_DB_MAX_CONNECTIONS)",
+                database.max_connections, database.min_connections
+            ));
+        }
+
+        let daily_task_at = problems.check(
+            parse_time(
+                &schedule.daily_task_at,
+                "schedule.daily_task_at",
+                "ROOT_DAILY_TASK_AT",
+            ),
+            NaiveTime::MIN,
+        );
+        let digest_day = problems.check(
+            parse_weekday(
+                &schedule.digest_day,
+                "schedule.digest_day",
+                "ROOT_DIGEST_DAY",
+            ),
+            Weekday::Mon,
+        );
+        let rollover_date = problems.check(parse_rollover_date(&schedule.rollover_date), None);
+
+        let level = logging.level.unwrap_or_else(|| {
+            let level = if server.env == "production" {
+                "info"
+            } else {
+                "trace"
+            };
+            level.to_string()
+        });
+        if let Err(e) = EnvFilter::try_new(&level) {
+            problems.add(format!(
+                "logging.level `{}` is invalid: {} (ROOT_LOG_LEVEL)",
+                level, e
+            ));
+        }
+
+        let validation_rules = problems.check(
+            ValidationRules::new(&members.roll_no_pattern)
+                .map(Some)
+                .map_err(|e| {
+                    format!(
+                        "members.roll_no_pattern must be a valid regex: {} (ROOT_ROLL_NO_PATTERN)",
+                        e
+                    )
+                }),
+            None,
+        );
+        let streak_deadline = problems.check(
+            parse_time(
+                &members.streak_deadline,
+                "members.streak_deadline",
+                "ROOT_STREAK_DEADLINE",
+            ),
+            NaiveTime::MIN,
+        );
+        let excluded_days = problems.check(
+            members
+                .streak_excluded_days
+                .iter()
+                .map(|day| {
+                    parse_weekday(
+                        day,
+                        "members.streak_excluded_days",
+                        "ROOT_STREAK_EXCLUDED_DAYS",
+                    )
+                })
+                .collect(),
+            Vec::new(),
+        );
+        let streak_rules = problems.check(
+            StreakRules::new(streak_deadline, excluded_days)
+                .map(Some)
+                .map_err(|e| {
+                    format!(
+                        "members.streak_excluded_days: {} (ROOT_STREAK_EXCLUDED_DAYS)",
+                        e
+                    )
+                }),
+            None,
+        );
+        let late_after = problems.check(
+            parse_time(&members.late_after, "members.late_after", "ROOT_LATE_AFTER"),
+            NaiveTime::MIN,
+        );
+
+        let streak_milestones = problems.check(
+            integrations
+                .discord
+                .streak_milestones
+                .iter()
+                .map(|milestone| {
+                    milestone.trim().parse().map_err(|_| {
+                        format!(
+                            "integrations.discord.streak_milestones must only contain numbers, not `{}` (ROOT_STREAK_MILESTONES)",
+                            milestone
+                        )
+                    })
+                })
+                .collect(),
+            Vec::new(),
+        );
+
+        let webhooks = integrations.webhooks;
+        if webhooks.max_attempts < 1 {
+            problems.add(
+                "integrations.webhooks.max_attempts must be at least 1 (ROOT_WEBHOOK_MAX_ATTEMPTS)"
+                    .to_string(),
+            );
+        }
+        if webhooks.retry_seconds < 1 {
+            problems.add(
+                "integrations.webhooks.retry_seconds must be at least 1 (ROOT_WEBHOOK_RETRY_SECONDS)"
+                    .to_string(),
+            );
+        }
+        if webhooks.retention_days < 1 {
+            problems.add(
+                "integrations.webhooks.retention_days must be at least 1 (ROOT_WEBHOOK_RETENTION_DAYS)"
+                    .to_string(),
+            );
+        }
+        if webhooks.poll_seconds == 0 {
+            problems.add(
+                "integrations.webhooks.poll_seconds must be at least 1 (ROOT_WEBHOOK_POLL_SECONDS)"
+                    .to_string(),
+            );
+        }
+
+        let smtp = integrations.smtp;
+        let security = problems.check(
+            smtp.security.parse().map_err(|_| {
+                format!(
+                    "integrations.smtp.security `{}` must be starttls, tls or none (ROOT_SMTP_SECURITY)",
+                    smtp.security
+                )
+            }),
+            SmtpSecurity::StartTls,
+        );
+        let mail_from = problems.check(
+            smtp.from.parse::<Mailbox>().map(Some).map_err(|e| {
+                format!(
+                    "integrations.smtp.from `{}` must be a mailbox such as `Root <root@amfoss.in>`: {} (ROOT_MAIL_FROM)",
+                    smtp.from, e
+                )
+            }),
+            None,
+        );
+
+        if !problems.0.is_empty() {
+            return Err(ConfigError(problems.0));
+        }
+
+        Ok(Self {
+            env: server.env,
+            host: server.host,
+            port: server.port,
+            secret_key,
+            member_token_secret,
+            admin_token: non_empty(server.admin_token),
+            public_url,
+            unsubscribe_secret,
+            metrics_token,
+            query_limits: QueryLimits {
+                max_depth: server.max_query_depth,
+                max_complexity: server.max_query_complexity,
+            },
+            rate_limit: RateLimitConfig {
+                burst: server.rate_limit_burst,
+                per_second: server.rate_limit_per_second,
+                trust_forwarded_for: server.trust_forwarded_for,
+            },
+            cors_origins,
+            shutdown_timeout: Duration::from_secs(server.shutdown_timeout_seconds),
+            database: DatabaseConfig {
+                url: database_url,
+                min_connections: database.min_connections,
+                max_connections: database.max_connections,
+                acquire_timeout: Duration::from_secs(database.acquire_timeout_seconds),
+            },
+            timezone,
+            daily_task_at,
+            digest_day,
+            rollover_rules: RolloverRules {
+                date: rollover_date,
+                undo_window: chrono::Duration::days(schedule.rollover_undo_days),
+            },
+            logging: LoggingConfig {
+                level,
+                file: non_empty(Some(logging.file)),
+            },
+            validation_rules: validation_rules.expect("Checked above"),
+            streak_rules: streak_rules.expect("Checked above"),
+            report_rules: ReportRules { late_after },
+            github_api_url: integrations.github.api_url,
+            github_token: non_empty(integrations.github.token),
+            streak_milestones,
+            delivery_rules: DeliveryRules {
+                max_attempts: webhooks.max_attempts,
+                retry_base: chrono::Duration::seconds(webhooks.retry_seconds),
+                poll_interval: Duration::from_secs(webhooks.poll_seconds),
+                retention: chrono::Duration::days(webhooks.retention_days),
+            },
+            smtp: non_empty(smtp.host).map(|host| SmtpSettings {
+                host,
+                port: smtp.port,
+                security,
+                username: non_empty(smtp.username),
+                password: non_empty(smtp.password),
+            }),
+            mail_from: mail_from.expect("Checked above"),
+        })
+    }
+
+    /// Makes `timezone()` return the configured timezone. Call once at startup.
+    pub fn apply_timezone(&self) {
+        let _ = TIMEZONE.set(self.timezone);
+    }
+}
+
+/// Collects problems while checking settings, so every one of them gets reported.
+#[derive(Default)]
+struct Problems(Vec<String>);
+
+impl Problems {
+    fn add(&mut self, problem: String) {
+        self.0.push(problem);
+    }
+
+    /// Records the problem, if any, and carries on with `placeholder`.
+    fn check<T>(&mut self, result: Result<T, String>, placeholder: T) -> T {
+        result.unwrap_or_else(|problem| {
+            self.add(problem);
+            placeholder
+        })
+    }
+}
+
+/// Points at the variable behind a setting that failed to parse, since errors only name the key.
+fn name_variable(e: config::ConfigError) -> ConfigError {
+    let problem = e.to_string();
+    let variable = ENV_OVERRIDES.iter().find(|(var, key, _)| {
+        problem.contains(&format!("`{}`", key)) && std::env::var(var).is_ok()
+    });
+    match variable {
+        Some((var, _, _)) => ConfigError(vec![format!("{} ({})", problem, var)]),
+        None => ConfigError(vec![problem]),
+    }
+}
+
+/// Splits a comma separated variable such as `Sat,Sun`. An empty value is an empty list.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Empty variables such as `ROOT_ADMIN_TOKEN=` count as unset.
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+fn required(value: Option<String>, key: &str, var: &str) -> Result<String, String> {
+    non_empty(value).ok_or_else(|| format!("{} must be set ({})", key, var))
+}
+
+fn parse_weekday(value: &str, key: &str, var: &str) -> Result<Weekday, String> {
+    value.parse().map_err(|_| {
+        format!(
+            "{} `{}` must be a weekday such as Mon ({})",
+            key, value, var
+        )
+    })
+}
+
+/// Origins are compared with the `Origin` header, which has no trailing slash.
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let origin = origin.trim_end_matches('/');
+    if !(origin.starts_with("http://") || origin.starts_with("https://")) {
+        return Err(format!(
+            "server.cors_origins must only contain origins such as https://home.amfoss.in, not `{}` (ROOT_CORS_ORIGINS)",
+            origin
+        ));
+    }
+    HeaderValue::from_str(origin).map_err(|_| {
+        format!(
+            "server.cors_origins contains `{}`, which isn't a valid header value (ROOT_CORS_ORIGINS)",
+            origin
+        )
+    })
+}
+
+fn parse_time(value: &str, key: &str, var: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|_| format!("{} `{}` must be formatted as HH:MM ({})", key, value, var))
+}
+
+/// `date` is a `MM-DD` day of the year, or empty to only roll over when an admin asks.
+fn parse_rollover_date(date: &str) -> Result<Option<(u32, u32)>, String> {
+    match date.trim() {
+        "" => Ok(None),
+        date => date
+            .split_once('-')
+            .and_then(|(month, day)| Some((month.parse().ok()?, day.parse().ok()?)))
+            .filter(|&(month, day)| chrono::NaiveDate::from_ymd_opt(2024, month, day).is_some())
+            .map(Some)
+            .ok_or_else(|| {
+                format!(
+                    "schedule.rollover_date `{}` must be formatted as MM-DD, or empty (ROOT_ROLLOVER_DATE)",
+                    date
+                )
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUIRED: &str = r#"
+        [server]
+        secret = "attendance"
+        member_token_secret = "members"
+
+        [database]
+        url = "postgres://localhost/root"
+    "#;
+
+    /// Checks `REQUIRED` with `extra` appended to its `[server]` table, or after it.
+    fn problems(server: &str, extra: &str) -> Vec<String> {
+        let toml = REQUIRED.replace("[database]", &format!("{}\n[database]", server)) + extra;
+        let settings: Settings = config::Config::builder()
+            .add_source(File::from_str(&toml, FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        match Config::check(settings) {
+            Ok(_) => Vec::new(),
+            Err(ConfigError(problems)) => problems,
+        }
+    }
+
+    #[test]
+    fn accepts_required_settings() {
+        assert_eq!(problems("", ""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_shared_secrets() {
+        let problems = problems(
+            r#"unsubscribe_secret = "members"
+            public_url = "https://root.amfoss.in""#,
+            "",
+        );
+        assert_eq!(
+            problems,
+            ["server.member_token_secret and server.unsubscribe_secret must be different secrets (ROOT_MEMBER_TOKEN_SECRET, ROOT_UNSUBSCRIBE_SECRET)"]
+        );
+    }
+
+    #[test]
+    fn rejects_rate_limits_letting_nothing_through() {
+        let problems = problems("rate_limit_burst = 0\nrate_limit_per_second = 0.0", "");
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("server.rate_limit_burst must be at least 1"));
+        assert!(problems[1].starts_with("server.rate_limit_per_second (0) must be above 0"));
+
+        assert_eq!(problems_for_rate("-1.5").len(), 1);
+        assert_eq!(problems_for_rate("nan").len(), 1);
+        assert_eq!(problems_for_rate("inf").len(), 1);
+        assert!(problems_for_rate("0.5").is_empty());
+    }
+
+    fn problems_for_rate(rate: &str) -> Vec<String> {
+        problems(&format!("rate_limit_per_second = {}", rate), "")
+    }
+
+    #[test]
+    fn rejects_webhook_intervals_below_one() {
+        let problems = problems(
+            "",
+            "[integrations.webhooks]\nretry_seconds = 0\nretention_days = -1\n",
+        );
+        assert_eq!(
+            problems,
+            [
+                "integrations.webhooks.retry_seconds must be at least 1 (ROOT_WEBHOOK_RETRY_SECONDS)",
+                "integrations.webhooks.retention_days must be at least 1 (ROOT_WEBHOOK_RETENTION_DAYS)",
+            ]
+        );
+    }
+}
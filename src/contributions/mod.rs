@@ -0,0 +1,345 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use chrono::{DateTime, Local, NaiveDateTime};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::PgPool;
+use tracing::{debug, error, warn};
+
+use crate::{
+    config::timezone,
+    metrics::{HmacCheck, Metrics},
+    models::{
+        contribution::{Contribution, ContributionKind, ForgeWebhook},
+        status_update::RecordStatusUpdateInput,
+    },
+    project_activity::Repository,
+    streaks::{record_status_update, refresh_streak, StreakRules},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone)]
+pub struct WebhookState {
+    pub pool: Arc<PgPool>,
+    pub streak_rules: StreakRules,
+    pub metrics: Metrics,
+}
+
+/// Routes receiving push and pull request webhooks from forges.
+pub fn router(state: WebhookState) -> Router {
+    Router::new()
+        .route("/webhooks/forge/{hook_id}", post(receive_webhook))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct RepositoryPayload {
+    full_name: String,
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct PushPayload {
+    repository: RepositoryPayload,
+    #[serde(default)]
+    commits: Vec<CommitPayload>,
+}
+
+#[derive(Deserialize)]
+struct CommitPayload {
+    id: String,
+    message: String,
+    url: Option<String>,
+    timestamp: String,
+    author: CommitAuthor,
+}
+
+#[derive(Deserialize)]
+struct CommitAuthor {
+    username: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PullRequestEventPayload {
+    action: String,
+    repository: RepositoryPayload,
+    pull_request: PullRequestPayload,
+}
+
+#[derive(Deserialize)]
+struct PullRequestPayload {
+    number: i64,
+    title: String,
+    html_url: Option<String>,
+    created_at: String,
+    merged_at: Option<String>,
+    user: ForgeUser,
+}
+
+#[derive(Deserialize)]
+struct ForgeUser {
+    login: String,
+}
+
+/// A contribution found in a payload, before its author is matched to a member.
+struct IncomingContribution {
+    username: String,
+    kind: ContributionKind,
+    reference: String,
+    title: String,
+    url: Option<String>,
+    contributed_at: NaiveDateTime,
+}
+
+type HandlerError = (StatusCode, Json<Value>);
+
+fn reject(status: StatusCode, message: &str) -> HandlerError {
+    (status, Json(json!({ "error": message })))
+}
+
+fn internal_error(e: sqlx::Error) -> HandlerError {
+    error!("Failed to process forge webhook: {:?}", e);
+    reject(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+}
+
+/// Accepts GitHub style webhooks, which Gitea and Forgejo also send. Payloads must be
+/// signed with the hook's secret in the `X-Hub-Signature-256` header. Unknown hooks are
+/// rejected like bad signatures, so callers can't tell which hook ids exist.
+async fn receive_webhook(
+    State(state): State<WebhookState>,
+    Path(hook_id): Path<i32>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Value>, HandlerError> {
+    let hook = sqlx::query_as::<_, ForgeWebhook>("SELECT * FROM ForgeWebhook WHERE hook_id = $1")
+        .bind(hook_id)
+        .fetch_optional(state.pool.as_ref())
+        .await
+        .map_err(internal_error)?;
+    let Some(hook) = hook.filter(|hook| verify_signature(&hook.secret, &headers, &body)) else {
+        warn!(
+            "Rejected webhook with invalid signature for hook #{}",
+            hook_id
+        );
+        state.metrics.record_hmac_failure(HmacCheck::ForgeWebhook);
+        return Err(reject(StatusCode::UNAUTHORIZED, "Invalid signature"));
+    };
+
+    let event = headers
+        .get("x-github-event")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let (repository, contributions) = match event {
+        "ping" => return Ok(Json(json!({ "credited": 0 }))),
+        "push" => parse_push(&body),
+        "pull_request" => parse_pull_request(&body),
+        _ => return Err(reject(StatusCode::BAD_REQUEST, "Unsupported event")),
+    }
+    .ok_or_else(|| reject(StatusCode::BAD_REQUEST, "Malformed payload"))?;
+
+    let project_id = match hook.project_id {
+        Some(project_id) => Some(project_id),
+        None => find_project(&repository.html_url, &state.pool)
+            .await
+            .map_err(internal_error)?,
+    };
+    let host = Repository::from_url(&repository.html_url)
+        .map(|repository| repository.host)
+        .unwrap_or_default();
+
+    let mut credited = 0;
+    for contribution in contributions {
+        let member_id = sqlx::query_scalar::<_, i32>(
+            "SELECT member_id FROM ForgeAccount
+            WHERE host = $1 AND LOWER(username) = LOWER($2) AND approved",
+        )
+        .bind(&host)
+        .bind(&contribution.username)
+        .fetch_optional(state.pool.as_ref())
+        .await
+        .map_err(internal_error)?;
+
+        let Some(member_id) = member_id else {
+            debug!(
+                "No approved member account for {} on {}, skipping contribution",
+                contribution.username, host
+            );
+            continue;
+        };
+
+        let recorded = record_contribution(
+            member_id,
+            project_id,
+            &repository.full_name,
+            &contribution,
+            &state.pool,
+        )
+        .await
+        .map_err(internal_error)?;
+
+        if let Some(recorded) = recorded {
+            credited += 1;
+            if hook.counts_as_status_update {
+                count_as_status_update(&recorded, &state)
+                    .await
+                    .map_err(internal_error)?;
+            }
+        }
+    }
+
+    Ok(Json(json!({ "credited": credited })))
+}
+
+fn verify_signature(secret: &str, headers: &HeaderMap, body: &[u8]) -> bool {
+    let Some(signature) = headers
+        .get("x-hub-signature-256")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("sha256="))
+        .and_then(|value| hex::decode(value).ok())
+    else {
+        return false;
+    };
+
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Converts forge timestamps such as `2025-01-15T10:00:00+05:30` to local time.
+fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|time| time.with_timezone(&timezone()).naive_local())
+}
+
+fn parse_push(body: &[u8]) -> Option<(RepositoryPayload, Vec<IncomingContribution>)> {
+    let payload: PushPayload = serde_json::from_slice(body).ok()?;
+
+    let contributions = payload
+        .commits
+        .into_iter()
+        .filter_map(|commit| {
+            Some(IncomingContribution {
+                username: commit.author.username?,
+                kind: ContributionKind::Commit,
+                reference: commit.id,
+                title: commit
+                    .message
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                url: commit.url,
+                contributed_at: parse_timestamp(&commit.timestamp)?,
+            })
+        })
+        .collect();
+
+    Some((payload.repository, contributions))
+}
+
+/// Only opened and merged pull requests are credited.
+fn parse_pull_request(body: &[u8]) -> Option<(RepositoryPayload, Vec<IncomingContribution>)> {
+    let payload: PullRequestEventPayload = serde_json::from_slice(body).ok()?;
+    let pull_request = payload.pull_request;
+
+    let credited = match (payload.action.as_str(), &pull_request.merged_at) {
+        ("opened", _) => Some((ContributionKind::PullRequestOpened, pull_request.created_at)),
+        ("closed", Some(merged_at)) => {
+            Some((ContributionKind::PullRequestMerged, merged_at.clone()))
+        }
+        _ => None,
+    };
+
+    let contributions = match credited {
+        Some((kind, timestamp)) => vec![IncomingContribution {
+            username: pull_request.user.login,
+            kind,
+            reference: pull_request.number.to_string(),
+            title: pull_request.title,
+            url: pull_request.html_url,
+            contributed_at: parse_timestamp(&timestamp)?,
+        }],
+        None => Vec::new(),
+    };
+
+    Some((payload.repository, contributions))
+}
+
+async fn find_project(repository_url: &str, pool: &PgPool) -> Result<Option<i32>, sqlx::Error> {
+    sqlx::query_scalar::<_, i32>(
+        "SELECT project_id FROM Project
+        WHERE LOWER(TRIM(TRAILING '/' FROM REGEXP_REPLACE(repository_url, '\\.git$', ''))) = LOWER($1)
+        ORDER BY project_id LIMIT 1",
+    )
+    .bind(repository_url.trim_end_matches('/'))
+    .fetch_optional(pool)
+    .await
+}
+
+/// Returns `None` if the contribution was already recorded by an earlier delivery.
+async fn record_contribution(
+    member_id: i32,
+    project_id: Option<i32>,
+    repository: &str,
+    contribution: &IncomingContribution,
+    pool: &PgPool,
+) -> Result<Option<Contribution>, sqlx::Error> {
+    sqlx::query_as::<_, Contribution>(
+        "INSERT INTO Contribution (member_id, project_id, kind, repository, reference, title, url, contributed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (kind, repository, reference) DO NOTHING
+        RETURNING *",
+    )
+    .bind(member_id)
+    .bind(project_id)
+    .bind(contribution.kind)
+    .bind(repository)
+    .bind(&contribution.reference)
+    .bind(&contribution.title)
+    .bind(&contribution.url)
+    .bind(contribution.contributed_at)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn count_as_status_update(
+    contribution: &Contribution,
+    state: &WebhookState,
+) -> Result<(), sqlx::Error> {
+    // Commit timestamps are whatever their author set, so a backdated commit could fill in a
+    // missed day. Contributions count for the day they reach Root instead.
+    let now = Local::now().with_timezone(&timezone()).naive_local();
+    let input = RecordStatusUpdateInput {
+        member_id: contribution.member_id,
+        date: now.date(),
+        source_message_id: Some(format!(
+            "{}#{}",
+            contribution.repository, contribution.reference
+        )),
+        content: contribution.title.clone(),
+        posted_at: None,
+    };
+    record_status_update(&input, now, &state.pool).await?;
+
+    refresh_streak(
+        contribution.member_id,
+        now,
+        &state.streak_rules,
+        &state.pool,
+    )
+    .await?;
+    Ok(())
+}
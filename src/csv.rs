@@ -0,0 +1,121 @@
+//! A minimal RFC 4180 reader and writer, enough for spreadsheets exported by hand.
+
+#[derive(Debug)]
+pub struct CsvError {
+    /// 1-based row number in the file.
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    /// 1-based row the record starts on, counting blank lines and line breaks in quoted fields.
+    pub row: usize,
+    pub fields: Vec<String>,
+}
+
+/// Splits `input` into records of fields. Fields may be quoted, with `""` standing for
+/// a quote inside a quoted field. Blank lines and a leading byte order mark are skipped.
+pub fn parse(input: &str) -> Result<Vec<Record>, CsvError> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut quoted_field_row = 0;
+    let mut row = 1;
+    let mut record_row = 1;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (in_quotes, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => in_quotes = false,
+            (true, c) => {
+                if c == '\n' {
+                    row += 1;
+                }
+                field.push(c);
+            }
+            (false, '"') if field.is_empty() => {
+                in_quotes = true;
+                quoted_field_row = row;
+            }
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                if !is_blank(&record) {
+                    records.push(Record {
+                        row: record_row,
+                        fields: std::mem::take(&mut record),
+                    });
+                }
+                record.clear();
+                row += 1;
+                record_row = row;
+            }
+            (false, c) => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(CsvError {
+            row: quoted_field_row,
+            message: "Quoted field is never closed".to_string(),
+        });
+    }
+
+    record.push(field);
+    if !is_blank(&record) {
+        records.push(Record {
+            row: record_row,
+            fields: record,
+        });
+    }
+    Ok(records)
+}
+
+fn is_blank(record: &[String]) -> bool {
+    record.iter().all(|field| field.trim().is_empty())
+}
+
+/// Joins fields into one CSV line, quoting fields that contain commas, quotes or line breaks.
+pub fn write_record<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_keep_the_row_they_start_on() {
+        let input = "name,bio\r\n\r\nAda,\"line one\nline two\"\n\nGrace,plain\n";
+        let records = parse(input).unwrap();
+
+        let rows: Vec<usize> = records.iter().map(|record| record.row).collect();
+        assert_eq!(rows, vec![1, 3, 6]);
+        assert_eq!(records[1].fields, vec!["Ada", "line one\nline two"]);
+    }
+
+    #[test]
+    fn unclosed_quotes_report_where_they_open() {
+        let error = parse("name\n\"Ada\n\nGrace\n").unwrap_err();
+        assert_eq!(error.row, 2);
+    }
+}
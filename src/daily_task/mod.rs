@@ -1,59 +1,209 @@
-use chrono::{Datelike, NaiveDate, NaiveTime};
-use chrono_tz::Asia::Kolkata;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use std::sync::Arc;
 use tokio::time::sleep_until;
 use tracing::{debug, error, info};
 
-use crate::models::member::Member;
+use crate::{
+    config::timezone,
+    email::digest::Digests,
+    health::SchedulerStatus,
+    lifecycle::{
+        apply_due_status_changes,
+        rollover::{roll_over, RolloverError, RolloverRules},
+    },
+    metrics::Metrics,
+    models::member::Member,
+    notifier::Notifier,
+    project_activity::{import_all_project_activity, ForgeClients},
+    reports::{generate_monthly_report, previous_month, ReportRules},
+    streaks::{refresh_streak, StreakRules},
+    supervisor::Shutdown,
+};
 
-pub async fn run_daily_task_at_midnight(pool: Arc<PgPool>) {
+/// Runs the daily task every day at `run_at`, recording each run in `scheduler` for `/ready`
+/// and in `metrics`. Returns once `shutdown` is requested, but never in the middle of a run.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_daily_task_at_midnight(
+    run_at: NaiveTime,
+    pool: Arc<PgPool>,
+    streak_rules: StreakRules,
+    rollover_rules: RolloverRules,
+    report_rules: ReportRules,
+    notifier: Notifier,
+    digests: Digests,
+    forge_clients: ForgeClients,
+    scheduler: SchedulerStatus,
+    metrics: Metrics,
+    shutdown: Shutdown,
+) {
+    let _running = scheduler.start(chrono::Utc::now().with_timezone(&timezone()).naive_local());
     loop {
-        let now = chrono::Utc::now().with_timezone(&Kolkata);
-        let naive_midnight =
-            NaiveTime::from_hms_opt(00, 30, 00).expect("Hardcoded time must be valid");
-        let today_midnight = now
-            .with_time(naive_midnight)
-            .single()
-            .expect("Hardcoded time must be valid");
-
-        let next_midnight = if now >= today_midnight {
-            today_midnight + chrono::Duration::days(1)
-        } else {
-            today_midnight
-        };
-        debug!("next_midnight: {}", next_midnight);
+        let now = chrono::Utc::now().with_timezone(&timezone());
+        let next_run_at = next_run(now, run_at);
+        debug!("next_run_at: {}", next_run_at);
 
-        let duration_until_midnight = next_midnight.signed_duration_since(now);
-        info!("Sleeping for {}", duration_until_midnight.num_seconds());
+        let duration_until_run = next_run_at.signed_duration_since(now);
+        info!("Sleeping for {}", duration_until_run.num_seconds());
         let sleep_duration =
-            tokio::time::Duration::from_secs(duration_until_midnight.num_seconds() as u64);
+            tokio::time::Duration::from_secs(duration_until_run.num_seconds() as u64);
 
-        sleep_until(tokio::time::Instant::now() + sleep_duration).await;
-        execute_daily_task(pool.clone()).await;
+        tokio::select! {
+            _ = sleep_until(tokio::time::Instant::now() + sleep_duration) => {}
+            _ = shutdown.requested() => {
+                info!("Stopping the daily task scheduler");
+                return;
+            }
+        }
+        let started = std::time::Instant::now();
+        let succeeded = execute_daily_task(
+            pool.clone(),
+            &streak_rules,
+            &rollover_rules,
+            &report_rules,
+            &notifier,
+            &digests,
+            &forge_clients,
+        )
+        .await;
+        metrics.record_daily_task(started.elapsed(), succeeded);
+        scheduler.record_run(
+            chrono::Utc::now().with_timezone(&timezone()).naive_local(),
+            succeeded,
+        );
+    }
+}
+
+/// The first time `run_at` comes around after `now`. Times skipped by a DST change run at the
+/// next valid time, and times repeated by one run on their first occurrence.
+fn next_run(now: DateTime<Tz>, run_at: NaiveTime) -> DateTime<Tz> {
+    let mut day = now.date_naive();
+    loop {
+        let mut at = day.and_time(run_at);
+        let local = loop {
+            match timezone().from_local_datetime(&at).earliest() {
+                Some(local) => break local,
+                None => at += chrono::Duration::minutes(1),
+            }
+        };
+        if local > now {
+            return local;
+        }
+        day = day.succ_opt().expect("Dates must not overflow");
     }
 }
 
 /// This function does a number of things, including:
+/// * Apply member status changes that became effective today
+/// * Roll over the academic year, if today is the configured rollover day
 /// * Insert new attendance records everyday for [`presense`](https://www.github.com/amfoss/presense) to update them later in the day.
 /// * Update the AttendanceSummary table
-async fn execute_daily_task(pool: Arc<PgPool>) {
-    // Members is queried outside of each function to avoid repetition
-    let members = sqlx::query_as::<_, Member>("SELECT * FROM Member")
+/// * Evaluate every member's status update streak
+/// * Post yesterday's absentees, late check-ins and streak changes to Discord
+/// * Email mentors a digest of their group's past week, on the configured digest day
+/// * Import yesterday's repository activity for every project
+/// * Generate last month's attendance report on the 1st
+///
+/// Returns whether every step succeeded. Failing to reach Discord, forges or the SMTP server
+/// is only logged, since those steps can be repeated through the API.
+async fn execute_daily_task(
+    pool: Arc<PgPool>,
+    streak_rules: &StreakRules,
+    rollover_rules: &RolloverRules,
+    report_rules: &ReportRules,
+    notifier: &Notifier,
+    digests: &Digests,
+    forge_clients: &ForgeClients,
+) -> bool {
+    let mut succeeded = true;
+    #[allow(deprecated)]
+    let today = chrono::Utc::now()
+        .with_timezone(&timezone())
+        .date()
+        .naive_local();
+    match apply_due_status_changes(today, &pool).await {
+        Ok(changed) => debug!("Applied status changes for {} members", changed),
+        Err(e) => {
+            error!("Failed to apply member status changes: {:?}", e);
+            succeeded = false;
+        }
+    }
+
+    if rollover_rules.is_rollover_day(today) {
+        let now = chrono::Utc::now().with_timezone(&timezone()).naive_local();
+        match roll_over(today.year(), now, true, false, &pool).await {
+            Ok(outcome) => info!(
+                "Rolled over academic year {}: {} promoted, {} graduated",
+                outcome.academic_year, outcome.promoted, outcome.graduated
+            ),
+            Err(RolloverError::AlreadyRolledOver(year)) => {
+                info!("Academic year {} was already rolled over", year)
+            }
+            Err(e) => {
+                error!("Failed to roll over academic year: {}", e);
+                succeeded = false;
+            }
+        }
+    }
+
+    // Members is queried outside of each function to avoid repetition.
+    // Only active members are expected in the lab or to post status updates.
+    let members = sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE status = 'active'")
         .fetch_all(&*pool)
         .await;
 
     match members {
-        Ok(members) => update_attendance(members, &pool).await,
-        // TODO: Handle this
-        Err(e) => error!("Failed to fetch members: {:?}", e),
+        Ok(members) => {
+            succeeded &= update_attendance(&members, &pool).await;
+            succeeded &= evaluate_streaks(&members, streak_rules, &pool).await;
+        }
+        Err(e) => {
+            error!("Failed to fetch members: {:?}", e);
+            succeeded = false;
+        }
     };
+
+    let now = chrono::Utc::now().with_timezone(&timezone()).naive_local();
+    notifier.send_daily_notifications(now, &pool).await;
+    digests
+        .send_weekly_digests(today, streak_rules, &pool)
+        .await;
+
+    let yesterday = today - chrono::Duration::days(1);
+    import_all_project_activity(yesterday, forge_clients, &pool).await;
+
+    if today.day() == 1 {
+        let now = chrono::Utc::now().with_timezone(&timezone()).naive_local();
+        match generate_monthly_report(
+            previous_month(today),
+            now,
+            report_rules,
+            streak_rules,
+            &pool,
+        )
+        .await
+        {
+            Ok(report) => info!(
+                "Generated monthly report for {}-{:02}",
+                report.year, report.month
+            ),
+            Err(e) => {
+                error!("Failed to generate monthly report: {:?}", e);
+                succeeded = false;
+            }
+        }
+    }
+
+    succeeded
 }
 
-async fn update_attendance(members: Vec<Member>, pool: &PgPool) {
+/// Returns whether a record could be inserted for every member.
+async fn update_attendance(members: &[Member], pool: &PgPool) -> bool {
+    let mut succeeded = true;
     #[allow(deprecated)]
     let today = chrono::Utc::now()
-        .with_timezone(&Kolkata)
+        .with_timezone(&timezone())
         .date()
         .naive_local();
     debug!("Updating attendance on {}", today);
@@ -84,19 +234,22 @@ async fn update_attendance(members: Vec<Member>, pool: &PgPool) {
                     "Failed to insert attendance for member ID: {}: {:?}",
                     member.member_id, e
                 );
+                succeeded = false;
             }
         }
         // This could have been called in `execute_daily_task()` but that would require us to loop through members twice.
         // Whether or not inserting attendance failed, Root will attempt to update AttendanceSummary. This can potentially fail too since insertion failed earlier. However, these two do not depend on each other and one of them failing is no reason to avoid trying the other.
         update_attendance_summary(member.member_id, pool).await;
     }
+
+    succeeded
 }
 
 async fn update_attendance_summary(member_id: i32, pool: &PgPool) {
     debug!("Updating summary for member #{}", member_id);
     #[allow(deprecated)]
     let today = chrono::Utc::now()
-        .with_timezone(&Kolkata)
+        .with_timezone(&timezone())
         .date()
         .naive_local();
     let yesterday = today - chrono::Duration::days(1);
@@ -151,7 +304,7 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
 
     match existing_days_attended {
         Ok(Some(days_attended)) => {
-            sqlx::query(
+            let result = sqlx::query(
                 r#"
                     UPDATE AttendanceSummary
                     SET days_attended = days_attended + 1
@@ -164,8 +317,14 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
             .bind(year)
             .bind(month)
             .execute(pool)
-            .await
-            .unwrap();
+            .await;
+            if let Err(e) = result {
+                error!(
+                    "Failed to update days_attended for member ID {}: {:?}",
+                    member_id, e
+                );
+                return;
+            }
 
             debug!(
                 "Updated days_attended for member ID: {}. New days_attended: {}",
@@ -174,7 +333,7 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
             );
         }
         Ok(None) => {
-            sqlx::query(
+            let result = sqlx::query(
                 r#"
                     INSERT INTO AttendanceSummary (member_id, year, month, days_attended)
                     VALUES ($1, $2, $3, 1)
@@ -184,8 +343,14 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
             .bind(year)
             .bind(month)
             .execute(pool)
-            .await
-            .unwrap();
+            .await;
+            if let Err(e) = result {
+                error!(
+                    "Failed to create attendance summary for member ID {}: {:?}",
+                    member_id, e
+                );
+                return;
+            }
 
             debug!(
                 "Created new streak for member ID: {} for the month.",
@@ -200,3 +365,72 @@ async fn update_days_attended(member_id: i32, today: NaiveDate, pool: &PgPool) {
         }
     }
 }
+
+/// Increments the streak of members who posted an update for the last working day before
+/// its deadline and resets everyone else's. Excluded days are skipped entirely.
+/// Returns whether every member's streak could be evaluated.
+async fn evaluate_streaks(members: &[Member], rules: &StreakRules, pool: &PgPool) -> bool {
+    let mut succeeded = true;
+    let now = chrono::Utc::now().with_timezone(&timezone()).naive_local();
+    debug!(
+        "Evaluating streaks for updates due on {}",
+        rules.last_due_day(now)
+    );
+
+    for member in members {
+        match refresh_streak(member.member_id, now, rules, pool).await {
+            Ok(streak) if streak.current_streak > 0 => {
+                debug!(
+                    "Streak for member ID: {} is now {}",
+                    member.member_id, streak.current_streak
+                );
+            }
+            Ok(_) => {
+                debug!("Streak reset for member ID: {}", member.member_id);
+            }
+            Err(e) => {
+                error!(
+                    "Failed to evaluate streak for member ID: {}: {:?}",
+                    member.member_id, e
+                );
+                succeeded = false;
+            }
+        }
+    }
+
+    succeeded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In the default timezone, `Asia/Kolkata`.
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+        timezone()
+            .with_ymd_and_hms(2025, 1, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn next_run_is_later_today_if_not_yet_passed() {
+        let run_at = NaiveTime::from_hms_opt(0, 30, 0).unwrap();
+        assert_eq!(next_run(at(15, 0, 10), run_at), at(15, 0, 30));
+    }
+
+    #[test]
+    fn next_run_is_tomorrow_once_passed() {
+        let run_at = NaiveTime::from_hms_opt(0, 30, 0).unwrap();
+        assert_eq!(next_run(at(15, 0, 30), run_at), at(16, 0, 30));
+        assert_eq!(next_run(at(15, 23, 59), run_at), at(16, 0, 30));
+    }
+
+    #[test]
+    fn next_run_crosses_months() {
+        let run_at = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        assert_eq!(
+            next_run(at(31, 12, 0), run_at),
+            timezone().with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap()
+        );
+    }
+}
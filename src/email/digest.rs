@@ -0,0 +1,463 @@
+use std::sync::Arc;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use handlebars::Handlebars;
+use lettre::{
+    message::{
+        header::{HeaderName, HeaderValue},
+        Mailbox, MultiPart,
+    },
+    Message,
+};
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use tracing::{debug, error, info};
+
+use super::{EmailError, Mailer, UnsubscribeLinks};
+use crate::{models::digest::GroupDigest, streaks::StreakRules};
+
+const HTML_TEMPLATE: &str = include_str!("templates/group_digest.html.hbs");
+const TEXT_TEMPLATE: &str = include_str!("templates/group_digest.txt.hbs");
+
+/// A member's week, as shown in a digest.
+#[derive(FromRow)]
+struct DigestMember {
+    name: String,
+    days_present: i32,
+    present_on_working_days: i32,
+    average_hours: Option<f64>,
+    status_updates: i32,
+    current_streak: i32,
+    missed_streak: i32,
+}
+
+/// A group along with its mentor, if the mentor has been set.
+#[derive(FromRow)]
+struct DigestGroup {
+    name: String,
+    mentor_id: Option<i32>,
+    mentor_name: Option<String>,
+    mentor_email: Option<String>,
+}
+
+/// Renders the weekly digests mentors receive about their groups, and sends them by email.
+#[derive(Clone)]
+pub struct Digests {
+    /// `None` if no SMTP server is configured, in which case digests can only be previewed.
+    mailer: Option<Arc<dyn Mailer>>,
+    from: Mailbox,
+    /// Digests are sent on this day, covering the seven days before it.
+    weekday: Weekday,
+    /// `None` if `ROOT_PUBLIC_URL` isn't set, in which case emails have no unsubscribe link.
+    links: Option<UnsubscribeLinks>,
+    html: Arc<Handlebars<'static>>,
+    text: Arc<Handlebars<'static>>,
+}
+
+impl Digests {
+    pub fn new(
+        mailer: Option<Arc<dyn Mailer>>,
+        from: Mailbox,
+        weekday: Weekday,
+        links: Option<UnsubscribeLinks>,
+    ) -> Self {
+        let mut html = Handlebars::new();
+        html.register_template_string("group_digest", HTML_TEMPLATE)
+            .expect("Digest templates must be valid");
+
+        let mut text = Handlebars::new();
+        text.register_escape_fn(handlebars::no_escape);
+        text.register_template_string("group_digest", TEXT_TEMPLATE)
+            .expect("Digest templates must be valid");
+
+        Self {
+            mailer,
+            from,
+            weekday,
+            links,
+            html: Arc::new(html),
+            text: Arc::new(text),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mailer.is_some()
+    }
+
+    /// Renders the digest of the group's week ending on `to`. `None` if the group doesn't exist.
+    pub async fn render(
+        &self,
+        group_id: i32,
+        to: NaiveDate,
+        streak_rules: &StreakRules,
+        pool: &PgPool,
+    ) -> Result<Option<GroupDigest>, EmailError> {
+        let Some(group) = sqlx::query_as::<_, DigestGroup>(
+            "SELECT g.name, g.mentor_id, m.name AS mentor_name, m.email AS mentor_email
+            FROM MemberGroup g LEFT JOIN Member m ON m.member_id = g.mentor_id
+            WHERE g.group_id = $1",
+        )
+        .bind(group_id)
+        .fetch_optional(pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let from = to - Duration::days(6);
+        let working_days: Vec<NaiveDate> = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .filter(|date| streak_rules.is_working_day(*date))
+            .collect();
+
+        let members = sqlx::query_as::<_, DigestMember>(
+            "SELECT m.name,
+                COUNT(a.attendance_id) FILTER (WHERE a.is_present)::int AS days_present,
+                COUNT(a.attendance_id) FILTER (WHERE a.is_present AND a.date = ANY($4))::int
+                    AS present_on_working_days,
+                AVG(EXTRACT(EPOCH FROM (a.time_out - a.time_in)) / 3600)
+                    FILTER (WHERE a.is_present)::float8 AS average_hours,
+                (SELECT COUNT(*) FROM StatusUpdate u
+                    WHERE u.member_id = m.member_id AND u.date BETWEEN $2 AND $3)::int
+                    AS status_updates,
+                COALESCE(s.current_streak, 0) AS current_streak,
+                COALESCE(s.missed_streak, 0) AS missed_streak
+            FROM Member m
+            LEFT JOIN Attendance a ON a.member_id = m.member_id AND a.date BETWEEN $2 AND $3
+            LEFT JOIN StatusUpdateStreak s ON s.member_id = m.member_id
+            WHERE m.group_id = $1 AND m.status = 'active'
+            GROUP BY m.member_id, s.current_streak, s.missed_streak
+            ORDER BY m.name",
+        )
+        .bind(group_id)
+        .bind(from)
+        .bind(to)
+        .bind(&working_days)
+        .fetch_all(pool)
+        .await?;
+
+        let percentage = |present: i32| match working_days.len() {
+            0 => 0.0,
+            days => 100.0 * present as f64 / days as f64,
+        };
+        let average_attendance = match members.len() {
+            0 => 0.0,
+            count => {
+                members
+                    .iter()
+                    .map(|member| percentage(member.present_on_working_days))
+                    .sum::<f64>()
+                    / count as f64
+            }
+        };
+
+        let unsubscribe_url = group
+            .mentor_id
+            .zip(self.links.as_ref())
+            .map(|(mentor_id, links)| links.url(mentor_id));
+        let context = json!({
+            "group": group.name,
+            "from": from.to_string(),
+            "to": to.to_string(),
+            "mentor": group.mentor_name.as_deref().unwrap_or("there"),
+            "workingDays": working_days.len(),
+            "averageAttendance": format!("{:.1}", average_attendance),
+            "membersOnStreak": members.iter().filter(|member| member.current_streak > 0).count(),
+            "count": members.len(),
+            "unsubscribeUrl": unsubscribe_url,
+            "members": members
+                .iter()
+                .map(|member| json!({
+                    "name": member.name,
+                    "daysPresent": member.days_present,
+                    "attendancePercentage": format!("{:.1}", percentage(member.present_on_working_days)),
+                    "averageHours": member.average_hours.map(|hours| format!("{:.1}", hours)),
+                    "statusUpdates": member.status_updates,
+                    "currentStreak": member.current_streak,
+                    "missedStreak": member.missed_streak,
+                }))
+                .collect::<Vec<_>>(),
+        });
+
+        let html = self
+            .html
+            .render("group_digest", &context)
+            .map_err(|e| EmailError::Template(e.to_string()))?;
+        let text = self
+            .text
+            .render("group_digest", &context)
+            .map_err(|e| EmailError::Template(e.to_string()))?;
+
+        Ok(Some(GroupDigest {
+            group_id,
+            from,
+            to,
+            recipient: group.mentor_email,
+            subject: format!("{}: week of {}", group.name, from.format("%-d %B %Y")),
+            html,
+            text,
+            mentor_id: group.mentor_id,
+            mentor_name: group.mentor_name,
+            unsubscribe_url,
+            members: members.len() as i32,
+        }))
+    }
+
+    /// Emails `digest` to the group's mentor. Returns `false` without sending anything if the
+    /// group has no mentor, or the mentor opted out of digests.
+    pub async fn send(&self, digest: &GroupDigest, pool: &PgPool) -> Result<bool, EmailError> {
+        let Some(mailer) = &self.mailer else {
+            return Err(EmailError::Disabled);
+        };
+        let (Some(mentor_id), Some(recipient)) = (digest.mentor_id, &digest.recipient) else {
+            return Ok(false);
+        };
+
+        let opted_out = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM DigestOptOut WHERE member_id = $1)",
+        )
+        .bind(mentor_id)
+        .fetch_one(pool)
+        .await?;
+        if opted_out {
+            return Ok(false);
+        }
+
+        let to = Mailbox::new(
+            digest.mentor_name.clone(),
+            recipient
+                .parse()
+                .map_err(|e: lettre::address::AddressError| EmailError::Message(e.to_string()))?,
+        );
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&digest.subject);
+        // Lets mail clients offer an unsubscribe button that opts out in one click (RFC 8058).
+        if let Some(url) = &digest.unsubscribe_url {
+            message = message
+                .raw_header(HeaderValue::new(
+                    HeaderName::new_from_ascii_str("List-Unsubscribe"),
+                    format!("<{}>", url),
+                ))
+                .raw_header(HeaderValue::new(
+                    HeaderName::new_from_ascii_str("List-Unsubscribe-Post"),
+                    "List-Unsubscribe=One-Click".to_string(),
+                ));
+        }
+        let message = message
+            .multipart(MultiPart::alternative_plain_html(
+                digest.text.clone(),
+                digest.html.clone(),
+            ))
+            .map_err(|e| EmailError::Message(e.to_string()))?;
+
+        mailer.send(message).await?;
+        Ok(true)
+    }
+
+    /// Sends every group's digest of the past week if `today` is the configured digest day.
+    /// Groups without active members are skipped, and a failure only affects its own group.
+    pub async fn send_weekly_digests(
+        &self,
+        today: NaiveDate,
+        streak_rules: &StreakRules,
+        pool: &PgPool,
+    ) {
+        if today.weekday() != self.weekday {
+            return;
+        }
+        if !self.is_enabled() {
+            debug!("Skipping weekly digests since SMTP isn't configured");
+            return;
+        }
+
+        let group_ids = match sqlx::query_scalar::<_, i32>(
+            "SELECT group_id FROM MemberGroup WHERE mentor_id IS NOT NULL ORDER BY group_id",
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(group_ids) => group_ids,
+            Err(e) => {
+                error!("Failed to fetch groups for weekly digests: {:?}", e);
+                return;
+            }
+        };
+
+        let to = today - Duration::days(1);
+        let mut sent = 0;
+        for group_id in group_ids {
+            let digest = match self.render(group_id, to, streak_rules, pool).await {
+                Ok(Some(digest)) if digest.members > 0 => digest,
+                Ok(_) => continue,
+                Err(e) => {
+                    error!("Failed to render digest of group #{}: {}", group_id, e);
+                    continue;
+                }
+            };
+            match self.send(&digest, pool).await {
+                Ok(true) => sent += 1,
+                Ok(false) => {}
+                Err(e) => error!("Failed to send digest of group #{}: {}", group_id, e),
+            }
+        }
+
+        info!("Sent {} weekly digests", sent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use chrono::NaiveTime;
+
+    use super::*;
+    use crate::email::opt_out;
+
+    /// Keeps sent emails instead of sending them.
+    #[derive(Default)]
+    struct RecordingMailer {
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Mailer for RecordingMailer {
+        async fn send(&self, message: Message) -> Result<(), EmailError> {
+            let formatted = String::from_utf8(message.formatted()).unwrap();
+            self.sent.lock().unwrap().push(formatted);
+            Ok(())
+        }
+    }
+
+    fn digests(mailer: Arc<RecordingMailer>) -> Digests {
+        Digests::new(
+            Some(mailer),
+            "amFOSS Root <root@amfoss.in>".parse().unwrap(),
+            Weekday::Mon,
+            Some(UnsubscribeLinks {
+                base_url: "https://root.amfoss.in/".to_string(),
+                secret: "unsubscribe".to_string(),
+            }),
+        )
+    }
+
+    fn streak_rules() -> StreakRules {
+        StreakRules::new(NaiveTime::MIN, vec![Weekday::Sun]).unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    /// A group mentored by Ravi, where Asha attended three mornings of the week ending on
+    /// the 19th. Returns the group and mentor ids.
+    async fn setup_group(pool: &PgPool) -> (i32, i32) {
+        let group_id: i32 =
+            sqlx::query_scalar("INSERT INTO MemberGroup (name) VALUES ('Web') RETURNING group_id")
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        let mut member_ids = Vec::new();
+        for (i, name) in ["Ravi", "Asha"].iter().enumerate() {
+            let member_id: i32 = sqlx::query_scalar(
+                "INSERT INTO Member (roll_no, name, email, sex, year, hostel, mac_address, discord_id, group_id)
+                VALUES ($1, $2, $3, 'F', 2, 'LH', $4, $5, $6) RETURNING member_id",
+            )
+            .bind(format!("AM.EN.U4CSE2100{}", i))
+            .bind(name)
+            .bind(format!("{}@amfoss.in", name.to_lowercase()))
+            .bind(format!("AA:BB:CC:DD:EE:0{}", i))
+            .bind(format!("12345678901234567{}", i))
+            .bind(group_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            member_ids.push(member_id);
+        }
+        sqlx::query("UPDATE MemberGroup SET mentor_id = $1 WHERE group_id = $2")
+            .bind(member_ids[0])
+            .bind(group_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        for day in [13, 14, 15] {
+            sqlx::query(
+                "INSERT INTO Attendance (member_id, date, is_present, time_in, time_out)
+                VALUES ($1, $2, TRUE, '09:00', '13:00')",
+            )
+            .bind(member_ids[1])
+            .bind(date(day))
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        (group_id, member_ids[0])
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a database, set DATABASE_URL and run with --ignored"]
+    async fn renders_and_sends_digest_to_mentor(pool: PgPool) {
+        let (group_id, mentor_id) = setup_group(&pool).await;
+        let mailer = Arc::new(RecordingMailer::default());
+        let digests = digests(mailer.clone());
+
+        let digest = digests
+            .render(group_id, date(19), &streak_rules(), &pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(digest.from, date(13));
+        assert_eq!(digest.recipient.as_deref(), Some("ravi@amfoss.in"));
+        assert_eq!(digest.subject, "Web: week of 13 January 2025");
+        assert_eq!(digest.members, 2);
+        let unsubscribe_url = format!(
+            "https://root.amfoss.in/email/unsubscribe?member={}&token=",
+            mentor_id
+        );
+        assert!(digest
+            .unsubscribe_url
+            .as_deref()
+            .is_some_and(|url| url.starts_with(&unsubscribe_url)));
+        assert!(digest
+            .text
+            .contains("Hi Ravi, here is how your group did over 6 working days."));
+        assert!(digest.text.contains("Average attendance was 25.0%"));
+        assert!(digest
+            .text
+            .contains("Asha\n  Days present: 3 (50.0%)\n  Average hours: 4.0"));
+        assert!(digest.html.contains("Asha"));
+
+        assert!(digests.send(&digest, &pool).await.unwrap());
+        let sent = mailer.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].contains("To: Ravi <ravi@amfoss.in>"));
+        assert!(sent[0].contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+        assert!(sent[0].contains(&format!("List-Unsubscribe: <{}", unsubscribe_url)));
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a database, set DATABASE_URL and run with --ignored"]
+    async fn skips_mentors_who_opted_out(pool: PgPool) {
+        let (group_id, mentor_id) = setup_group(&pool).await;
+        opt_out(mentor_id, &pool).await.unwrap();
+        let mailer = Arc::new(RecordingMailer::default());
+        let digests = digests(mailer.clone());
+
+        let digest = digests
+            .render(group_id, date(19), &streak_rules(), &pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!digests.send(&digest, &pool).await.unwrap());
+
+        // Digests go out on Mondays, covering the week before.
+        digests
+            .send_weekly_digests(date(20), &streak_rules(), &pool)
+            .await;
+        assert!(mailer.sent.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Html,
+    routing::get,
+    Router,
+};
+use chrono::Local;
+use hmac::{Hmac, Mac};
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
+use serde::Deserialize;
+use sha2::Sha256;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::{
+    config::timezone,
+    metrics::{HmacCheck, Metrics},
+};
+
+pub mod digest;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug)]
+pub enum EmailError {
+    /// No SMTP server is configured.
+    Disabled,
+    Database(sqlx::Error),
+    Template(String),
+    /// The email couldn't be built, e.g. because of an invalid address.
+    Message(String),
+    Smtp(lettre::transport::smtp::Error),
+}
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmailError::Disabled => write!(f, "Email is not configured"),
+            EmailError::Database(e) => write!(f, "Database error: {}", e),
+            EmailError::Template(e) => write!(f, "Failed to render email: {}", e),
+            EmailError::Message(e) => write!(f, "Invalid email: {}", e),
+            EmailError::Smtp(e) => write!(f, "Sending email failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EmailError {}
+
+impl From<sqlx::Error> for EmailError {
+    fn from(e: sqlx::Error) -> Self {
+        EmailError::Database(e)
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for EmailError {
+    fn from(e: lettre::transport::smtp::Error) -> Self {
+        EmailError::Smtp(e)
+    }
+}
+
+/// Sends emails. Implemented by [`SmtpMailer`], and by stand-ins when testing without a mail server.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: Message) -> Result<(), EmailError>;
+}
+
+/// How the connection to the SMTP server is secured.
+#[derive(Clone, Copy, Debug)]
+pub enum SmtpSecurity {
+    /// Upgrades a plain connection with STARTTLS, usually on port 587.
+    StartTls,
+    /// Connects over TLS, usually on port 465.
+    Tls,
+    /// Plain text, only meant for local mail sinks.
+    None,
+}
+
+impl std::str::FromStr for SmtpSecurity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" => Ok(SmtpSecurity::Tls),
+            "none" => Ok(SmtpSecurity::None),
+            _ => Err(format!("Unknown SMTP security {}", value)),
+        }
+    }
+}
+
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Sends emails through an SMTP server, such as a local sink while developing.
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(settings: SmtpSettings) -> Result<Self, EmailError> {
+        let builder = match settings.security {
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host)?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host)?,
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.host)
+            }
+        }
+        .port(settings.port);
+
+        let builder = match (settings.username, settings.password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username, password))
+            }
+            _ => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: Message) -> Result<(), EmailError> {
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Builds and checks the links in emails that let members opt out of them. Links carry the
+/// hex HMAC-SHA256 of `unsubscribe.<member_id>` keyed with `ROOT_UNSUBSCRIBE_SECRET`, so
+/// they can't be forged.
+#[derive(Clone)]
+pub struct UnsubscribeLinks {
+    /// Where Root is publicly reachable, e.g. `https://root.amfoss.in`.
+    pub base_url: String,
+    pub secret: String,
+}
+
+impl UnsubscribeLinks {
+    fn mac(&self, member_id: i32) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(format!("unsubscribe.{}", member_id).as_bytes());
+        mac
+    }
+
+    pub fn url(&self, member_id: i32) -> String {
+        let token = hex::encode(self.mac(member_id).finalize().into_bytes());
+        format!(
+            "{}/email/unsubscribe?member={}&token={}",
+            self.base_url.trim_end_matches('/'),
+            member_id,
+            token
+        )
+    }
+
+    pub fn verify(&self, member_id: i32, token: &str) -> bool {
+        hex::decode(token)
+            .ok()
+            .is_some_and(|token| self.mac(member_id).verify_slice(&token).is_ok())
+    }
+}
+
+/// Stops sending weekly digests to a member. Doing it again changes nothing.
+pub async fn opt_out(member_id: i32, pool: &PgPool) -> Result<(), sqlx::Error> {
+    let now = Local::now().with_timezone(&timezone()).naive_local();
+    sqlx::query(
+        "INSERT INTO DigestOptOut (member_id, opted_out_at) VALUES ($1, $2)
+        ON CONFLICT (member_id) DO NOTHING",
+    )
+    .bind(member_id)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct EmailState {
+    pub pool: Arc<PgPool>,
+    pub links: Option<UnsubscribeLinks>,
+    pub metrics: Metrics,
+}
+
+/// The route behind unsubscribe links in emails. Opening a link only asks for confirmation,
+/// since mail scanners follow links. Members are opted out by the form it shows, or by mail
+/// clients posting to the link as RFC 8058 describes for `List-Unsubscribe-Post`.
+pub fn router(state: EmailState) -> Router {
+    Router::new()
+        .route(
+            "/email/unsubscribe",
+            get(confirm_unsubscribe).post(unsubscribe),
+        )
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct UnsubscribeParams {
+    member: i32,
+    token: String,
+}
+
+type Page = (StatusCode, Html<String>);
+
+fn page(status: StatusCode, body: &str) -> Page {
+    (
+        status,
+        Html(format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Unsubscribe</title></head><body>{}</body></html>\n",
+            body
+        )),
+    )
+}
+
+fn check_link(state: &EmailState, params: &UnsubscribeParams) -> Result<(), Page> {
+    let invalid = || {
+        page(
+            StatusCode::FORBIDDEN,
+            "<p>This unsubscribe link is invalid.</p>",
+        )
+    };
+    let Some(links) = &state.links else {
+        return Err(invalid());
+    };
+    if !links.verify(params.member, &params.token) {
+        state.metrics.record_hmac_failure(HmacCheck::Unsubscribe);
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+async fn confirm_unsubscribe(
+    State(state): State<EmailState>,
+    Query(params): Query<UnsubscribeParams>,
+) -> Page {
+    if let Err(page) = check_link(&state, &params) {
+        return page;
+    }
+
+    // The token was just verified, so it's only hex digits and safe to put in the page.
+    page(
+        StatusCode::OK,
+        &format!(
+            "<p>Stop receiving weekly digests from Root?</p>\
+            <form method=\"post\" action=\"?member={}&amp;token={}\">\
+            <button type=\"submit\">Unsubscribe</button></form>",
+            params.member, params.token
+        ),
+    )
+}
+
+async fn unsubscribe(
+    State(state): State<EmailState>,
+    Query(params): Query<UnsubscribeParams>,
+) -> Page {
+    if let Err(page) = check_link(&state, &params) {
+        return page;
+    }
+
+    match opt_out(params.member, &state.pool).await {
+        Ok(()) => page(
+            StatusCode::OK,
+            "<p>You won't receive weekly digests from Root anymore.</p>",
+        ),
+        Err(e) => {
+            error!(
+                "Failed to opt member #{} out of digests: {:?}",
+                params.member, e
+            );
+            page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "<p>Something went wrong, please try again later.</p>",
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links() -> UnsubscribeLinks {
+        UnsubscribeLinks {
+            base_url: "https://root.amfoss.in/".to_string(),
+            secret: "unsubscribe".to_string(),
+        }
+    }
+
+    #[test]
+    fn verifies_its_own_links() {
+        let links = links();
+        let url = links.url(7);
+        let token = url
+            .strip_prefix("https://root.amfoss.in/email/unsubscribe?member=7&token=")
+            .unwrap();
+
+        assert!(links.verify(7, token));
+        assert!(!links.verify(8, token));
+        assert!(!links.verify(7, "not hex"));
+        let other = UnsubscribeLinks {
+            secret: "other".to_string(),
+            ..links
+        };
+        assert!(!other.verify(7, token));
+    }
+}
@@ -0,0 +1,516 @@
+use std::{sync::Arc, time::Instant};
+
+use chrono::{Duration, Local, NaiveDateTime};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::{FromRow, PgPool};
+use tracing::{debug, error, info, warn};
+
+use super::prune_events;
+use crate::config::timezone;
+use crate::models::webhook::WebhookEventType;
+use crate::supervisor::Shutdown;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Deliveries claimed at once by the dispatcher.
+const BATCH_SIZE: i64 = 20;
+
+/// How long a receiver gets to respond.
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Retries never wait longer than this.
+const MAX_RETRY_DELAY: Duration = Duration::hours(6);
+
+/// Settings for sending webhooks.
+#[derive(Clone)]
+pub struct DeliveryRules {
+    /// A delivery is marked failed after this many unsuccessful attempts.
+    pub max_attempts: i32,
+    /// Wait before the first retry, doubled after every further failure.
+    pub retry_base: Duration,
+    /// How often the outbox is checked for due deliveries.
+    pub poll_interval: std::time::Duration,
+    /// Events older than this are deleted once they are no longer pending.
+    pub retention: Duration,
+}
+
+impl DeliveryRules {
+    /// Wait before the next attempt of a delivery that has failed `attempts` times.
+    pub fn retry_delay(&self, attempts: i32) -> Duration {
+        let factor = 2_i32.saturating_pow(attempts.saturating_sub(1).max(0) as u32);
+        self.retry_base
+            .checked_mul(factor)
+            .unwrap_or(MAX_RETRY_DELAY)
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// A delivery claimed for sending, with what's needed to send it.
+#[derive(FromRow)]
+struct DueDelivery {
+    delivery_id: i32,
+    /// Including the attempt it was claimed for.
+    attempts: i32,
+    event_id: i32,
+    event_type: WebhookEventType,
+    payload: Value,
+    created_at: NaiveDateTime,
+    url: String,
+    secret: String,
+}
+
+/// The body of every webhook request.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope<'a> {
+    id: i32,
+    #[serde(rename = "type")]
+    event_type: &'static str,
+    created_at: NaiveDateTime,
+    data: &'a Value,
+}
+
+/// What happened when sending a request.
+struct AttemptResult {
+    status_code: Option<i32>,
+    error: Option<String>,
+    duration_ms: i32,
+}
+
+impl AttemptResult {
+    fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Sends due deliveries every `poll_interval`, and prunes old events once a day, until
+/// `shutdown` is requested.
+pub async fn run_webhook_dispatcher(pool: Arc<PgPool>, rules: DeliveryRules, shutdown: Shutdown) {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent("amfoss-root")
+        .build()
+        .expect("HTTP client must be built properly.");
+    let mut next_prune = Instant::now();
+
+    loop {
+        if Instant::now() >= next_prune {
+            let before = Local::now().with_timezone(&timezone()).naive_local() - rules.retention;
+            match prune_events(before, &pool).await {
+                Ok(pruned) => debug!("Pruned {} webhook events", pruned),
+                Err(e) => error!("Failed to prune webhook events: {:?}", e),
+            }
+            next_prune += std::time::Duration::from_secs(24 * 60 * 60);
+        }
+
+        // Keep going while there's a backlog, instead of waiting between full batches.
+        loop {
+            match deliver_due(&client, &rules, &pool).await {
+                Ok(sent) if sent as i64 == BATCH_SIZE && !shutdown.is_requested() => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    error!("Failed to deliver webhooks: {:?}", e);
+                    break;
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(rules.poll_interval) => {}
+            _ = shutdown.requested() => return,
+        }
+    }
+}
+
+/// Sends a batch of deliveries whose next attempt is due. Returns how many were attempted.
+pub async fn deliver_due(
+    client: &reqwest::Client,
+    rules: &DeliveryRules,
+    pool: &PgPool,
+) -> Result<usize, sqlx::Error> {
+    let now = Local::now().with_timezone(&timezone()).naive_local();
+
+    // Deliveries whose attempts ran out without a result being recorded, e.g. because Root
+    // crashed while sending them every time.
+    sqlx::query(
+        "UPDATE WebhookDelivery SET status = 'failed'
+        WHERE status = 'pending' AND attempts >= $1 AND next_attempt_at <= $2",
+    )
+    .bind(rules.max_attempts)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    // Claimed deliveries are pushed back and their attempt counted, so a crash while sending
+    // only delays them, and a delivery crashing Root every time still runs out of attempts.
+    let due = sqlx::query_as::<_, DueDelivery>(
+        "WITH due AS (
+            SELECT d.delivery_id FROM WebhookDelivery d
+            JOIN WebhookSubscription s ON s.subscription_id = d.subscription_id
+            WHERE d.status = 'pending' AND d.next_attempt_at <= $1 AND s.active
+                AND d.attempts < $4
+            ORDER BY d.next_attempt_at
+            LIMIT $3
+            FOR UPDATE OF d SKIP LOCKED
+        )
+        UPDATE WebhookDelivery d SET next_attempt_at = $2, attempts = d.attempts + 1
+        FROM due, WebhookEvent e, WebhookSubscription s
+        WHERE d.delivery_id = due.delivery_id
+            AND e.event_id = d.event_id
+            AND s.subscription_id = d.subscription_id
+        RETURNING d.delivery_id, d.attempts, e.event_id, e.event_type, e.payload, e.created_at,
+            s.url, s.secret",
+    )
+    .bind(now)
+    .bind(now + rules.retry_delay(1))
+    .bind(BATCH_SIZE)
+    .bind(rules.max_attempts)
+    .fetch_all(pool)
+    .await?;
+
+    for delivery in &due {
+        let attempted_at = Local::now().with_timezone(&timezone()).naive_local();
+        let result = send(client, delivery).await;
+        record_attempt(delivery, attempted_at, &result, rules, pool).await?;
+    }
+
+    Ok(due.len())
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `<timestamp>.<body>`, keyed with the secret.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn send(client: &reqwest::Client, delivery: &DueDelivery) -> AttemptResult {
+    let body = serde_json::to_string(&Envelope {
+        id: delivery.event_id,
+        event_type: delivery.event_type.as_str(),
+        created_at: delivery.created_at,
+        data: &delivery.payload,
+    })
+    .expect("Envelope must serialize to JSON");
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let started = Instant::now();
+    let response = client
+        .post(&delivery.url)
+        .header("Content-Type", "application/json")
+        .header("X-Root-Event", delivery.event_type.as_str())
+        .header("X-Root-Delivery", delivery.delivery_id)
+        .header("X-Root-Timestamp", timestamp)
+        .header("X-Root-Signature", sign(&delivery.secret, timestamp, &body))
+        .body(body)
+        .send()
+        .await;
+    let duration_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            AttemptResult {
+                status_code: Some(status.as_u16() as i32),
+                error: (!status.is_success())
+                    .then(|| format!("Receiver responded with {}", status)),
+                duration_ms,
+            }
+        }
+        Err(e) => AttemptResult {
+            status_code: None,
+            error: Some(e.to_string()),
+            duration_ms,
+        },
+    }
+}
+
+async fn record_attempt(
+    delivery: &DueDelivery,
+    attempted_at: NaiveDateTime,
+    result: &AttemptResult,
+    rules: &DeliveryRules,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let attempts = delivery.attempts;
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO WebhookDeliveryAttempt (delivery_id, attempted_at, status_code, error, duration_ms)
+        VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(delivery.delivery_id)
+    .bind(attempted_at)
+    .bind(result.status_code)
+    .bind(&result.error)
+    .bind(result.duration_ms)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.succeeded() {
+        debug!(
+            "Delivered {} event #{} to {}",
+            delivery.event_type.as_str(),
+            delivery.event_id,
+            delivery.url
+        );
+        sqlx::query(
+            "UPDATE WebhookDelivery SET status = 'delivered', attempts = $2, delivered_at = $3
+            WHERE delivery_id = $1",
+        )
+        .bind(delivery.delivery_id)
+        .bind(attempts)
+        .bind(attempted_at)
+        .execute(&mut *tx)
+        .await?;
+    } else if attempts >= rules.max_attempts {
+        warn!(
+            "Giving up on delivery #{} to {} after {} attempts",
+            delivery.delivery_id, delivery.url, attempts
+        );
+        sqlx::query(
+            "UPDATE WebhookDelivery SET status = 'failed', attempts = $2 WHERE delivery_id = $1",
+        )
+        .bind(delivery.delivery_id)
+        .bind(attempts)
+        .execute(&mut *tx)
+        .await?;
+    } else {
+        let next_attempt_at = attempted_at + rules.retry_delay(attempts);
+        info!(
+            "Delivery #{} to {} failed, retrying at {}",
+            delivery.delivery_id, delivery.url, next_attempt_at
+        );
+        sqlx::query(
+            "UPDATE WebhookDelivery SET attempts = $2, next_attempt_at = $3 WHERE delivery_id = $1",
+        )
+        .bind(delivery.delivery_id)
+        .bind(attempts)
+        .bind(next_attempt_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+    use serde_json::json;
+
+    use super::*;
+
+    /// A local webhook receiver answering with `statuses` in turn, then `200 OK`.
+    #[derive(Clone, Default)]
+    struct Receiver {
+        statuses: Arc<Mutex<VecDeque<StatusCode>>>,
+        received: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+    }
+
+    impl Receiver {
+        async fn start(statuses: &[StatusCode]) -> (Self, String) {
+            let receiver = Self {
+                statuses: Arc::new(Mutex::new(statuses.iter().copied().collect())),
+                ..Self::default()
+            };
+            let app = Router::new()
+                .route("/hook", post(receive))
+                .with_state(receiver.clone());
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/hook", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            (receiver, url)
+        }
+
+        fn received(&self) -> Vec<(HeaderMap, String)> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        receiver.received.lock().unwrap().push((headers, body));
+        receiver
+            .statuses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or(StatusCode::OK)
+    }
+
+    fn rules() -> DeliveryRules {
+        DeliveryRules {
+            max_attempts: 3,
+            retry_base: Duration::zero(),
+            poll_interval: std::time::Duration::from_secs(1),
+            retention: Duration::days(1),
+        }
+    }
+
+    fn client() -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap()
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+        headers.get(name).unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_maximum() {
+        let rules = DeliveryRules {
+            retry_base: Duration::minutes(1),
+            ..rules()
+        };
+        assert_eq!(rules.retry_delay(0), Duration::minutes(1));
+        assert_eq!(rules.retry_delay(1), Duration::minutes(1));
+        assert_eq!(rules.retry_delay(2), Duration::minutes(2));
+        assert_eq!(rules.retry_delay(4), Duration::minutes(8));
+        assert_eq!(rules.retry_delay(10), MAX_RETRY_DELAY);
+        assert_eq!(rules.retry_delay(i32::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        assert_eq!(
+            sign("secret", 1700000000, "{}"),
+            "sha256=b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163"
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_signed_requests_and_reports_failures() {
+        let (receiver, url) = Receiver::start(&[StatusCode::INTERNAL_SERVER_ERROR]).await;
+        let delivery = DueDelivery {
+            delivery_id: 4,
+            attempts: 1,
+            event_id: 2,
+            event_type: WebhookEventType::Ping,
+            payload: json!({ "hello": "world" }),
+            created_at: NaiveDateTime::default(),
+            url,
+            secret: "secret".to_string(),
+        };
+
+        let failed = send(&client(), &delivery).await;
+        assert_eq!(failed.status_code, Some(500));
+        assert!(!failed.succeeded());
+        let succeeded = send(&client(), &delivery).await;
+        assert_eq!(succeeded.status_code, Some(200));
+        assert!(succeeded.succeeded());
+
+        let received = receiver.received();
+        assert_eq!(received.len(), 2);
+        let (headers, body) = &received[0];
+        let timestamp: i64 = header(headers, "x-root-timestamp").parse().unwrap();
+        assert_eq!(
+            header(headers, "x-root-signature"),
+            sign("secret", timestamp, body)
+        );
+        assert_eq!(header(headers, "x-root-event"), "ping");
+        assert_eq!(header(headers, "x-root-delivery"), "4");
+        let body: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["id"], 2);
+        assert_eq!(body["data"], json!({ "hello": "world" }));
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a database, set DATABASE_URL and run with --ignored"]
+    async fn retries_failed_deliveries_and_logs_attempts(pool: PgPool) {
+        let (receiver, url) = Receiver::start(&[StatusCode::INTERNAL_SERVER_ERROR]).await;
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        let delivery_id: i32 = sqlx::query_scalar(
+            "WITH s AS (
+                INSERT INTO WebhookSubscription (url, secret, created_at)
+                VALUES ($1, 'secret', $2) RETURNING subscription_id
+            ), e AS (
+                INSERT INTO WebhookEvent (event_type, payload, created_at)
+                VALUES ('ping', '{}', $2) RETURNING event_id
+            )
+            INSERT INTO WebhookDelivery (event_id, subscription_id, next_attempt_at)
+            SELECT event_id, subscription_id, $2 FROM s, e RETURNING delivery_id",
+        )
+        .bind(&url)
+        .bind(now)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let client = client();
+        assert_eq!(deliver_due(&client, &rules(), &pool).await.unwrap(), 1);
+        assert_eq!(deliver_due(&client, &rules(), &pool).await.unwrap(), 1);
+        assert_eq!(deliver_due(&client, &rules(), &pool).await.unwrap(), 0);
+
+        let (status, attempts): (String, i32) = sqlx::query_as(
+            "SELECT status::text, attempts FROM WebhookDelivery WHERE delivery_id = $1",
+        )
+        .bind(delivery_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((status.as_str(), attempts), ("delivered", 2));
+
+        let log: Vec<(Option<i32>, Option<String>)> = sqlx::query_as(
+            "SELECT status_code, error FROM WebhookDeliveryAttempt
+            WHERE delivery_id = $1 ORDER BY attempt_id",
+        )
+        .bind(delivery_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            log,
+            [
+                (
+                    Some(500),
+                    Some("Receiver responded with 500 Internal Server Error".to_string())
+                ),
+                (Some(200), None),
+            ]
+        );
+        assert_eq!(receiver.received().len(), 2);
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a database, set DATABASE_URL and run with --ignored"]
+    async fn fails_deliveries_out_of_attempts(pool: PgPool) {
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        sqlx::query(
+            "WITH s AS (
+                INSERT INTO WebhookSubscription (url, secret, created_at)
+                VALUES ('http://127.0.0.1:1/hook', 'secret', $1) RETURNING subscription_id
+            ), e AS (
+                INSERT INTO WebhookEvent (event_type, payload, created_at)
+                VALUES ('ping', '{}', $1) RETURNING event_id
+            )
+            INSERT INTO WebhookDelivery (event_id, subscription_id, attempts, next_attempt_at)
+            SELECT event_id, subscription_id, 3, $1 FROM s, e",
+        )
+        .bind(now)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(deliver_due(&client(), &rules(), &pool).await.unwrap(), 0);
+        let status: String = sqlx::query_scalar("SELECT status::text FROM WebhookDelivery")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "failed");
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{Local, NaiveDateTime};
+use serde_json::{json, Value};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::config::timezone;
+use crate::models::{
+    attendance::Attendance,
+    member::Member,
+    project::Project,
+    status_update_streak::StatusUpdateStreak,
+    webhook::{WebhookDelivery, WebhookEventType},
+};
+
+pub mod delivery;
+
+/// Something that happened in Root, which webhook subscribers can be notified of.
+pub enum Event<'a> {
+    MemberCreated(&'a Member),
+    MemberUpdated(&'a Member),
+    AttendanceMarked(&'a Attendance),
+    StreakBroken {
+        /// The length of the streak before it broke.
+        broken_streak: i32,
+        streak: &'a StatusUpdateStreak,
+    },
+    ProjectUpdated(&'a Project),
+    ProjectDeleted(&'a Project),
+}
+
+impl Event<'_> {
+    pub fn event_type(&self) -> WebhookEventType {
+        match self {
+            Event::MemberCreated(_) => WebhookEventType::MemberCreated,
+            Event::MemberUpdated(_) => WebhookEventType::MemberUpdated,
+            Event::AttendanceMarked(_) => WebhookEventType::AttendanceMarked,
+            Event::StreakBroken { .. } => WebhookEventType::StreakBroken,
+            Event::ProjectUpdated(_) => WebhookEventType::ProjectUpdated,
+            Event::ProjectDeleted(_) => WebhookEventType::ProjectDeleted,
+        }
+    }
+
+    /// The event's `data`, with the same field names as the GraphQL API.
+    pub fn payload(&self) -> Value {
+        let payload = match self {
+            Event::MemberCreated(member) | Event::MemberUpdated(member) => {
+                serde_json::to_value(member)
+            }
+            Event::AttendanceMarked(attendance) => serde_json::to_value(attendance),
+            Event::StreakBroken {
+                broken_streak,
+                streak,
+            } => Ok(json!({
+                "memberId": streak.member_id,
+                "brokenStreak": broken_streak,
+                "maxStreak": streak.max_streak,
+                "missedStreak": streak.missed_streak,
+            })),
+            Event::ProjectUpdated(project) | Event::ProjectDeleted(project) => {
+                serde_json::to_value(project)
+            }
+        };
+        payload.expect("Models must serialize to JSON")
+    }
+}
+
+/// Adds `event` to the outbox and queues a delivery to every active subscription that wants it.
+/// Pass the transaction making the change, so the event is only sent if the change is committed.
+pub async fn emit<'e>(event: Event<'_>, executor: impl PgExecutor<'e>) -> Result<(), sqlx::Error> {
+    enqueue(event.event_type(), event.payload(), None, executor).await?;
+    Ok(())
+}
+
+/// Queues a `ping` event to a single subscription, whether or not it is active.
+pub async fn ping(
+    subscription_id: i32,
+    pool: &PgPool,
+) -> Result<Option<WebhookDelivery>, sqlx::Error> {
+    let deliveries = enqueue(
+        WebhookEventType::Ping,
+        json!({ "subscriptionId": subscription_id }),
+        Some(subscription_id),
+        pool,
+    )
+    .await?;
+    Ok(deliveries.into_iter().next())
+}
+
+async fn enqueue<'e>(
+    event_type: WebhookEventType,
+    payload: Value,
+    subscription_id: Option<i32>,
+    executor: impl PgExecutor<'e>,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    let now = Local::now().with_timezone(&timezone()).naive_local();
+
+    sqlx::query_as::<_, WebhookDelivery>(
+        "WITH event AS (
+            INSERT INTO WebhookEvent (event_type, payload, created_at)
+            VALUES ($1, $2, $3) RETURNING event_id
+        )
+        INSERT INTO WebhookDelivery (event_id, subscription_id, next_attempt_at)
+        SELECT event.event_id, s.subscription_id, $3
+        FROM event, WebhookSubscription s
+        WHERE CASE WHEN $4::int IS NULL
+            THEN s.active AND (cardinality(s.events) = 0 OR $1 = ANY(s.events))
+            ELSE s.subscription_id = $4 END
+        RETURNING *",
+    )
+    .bind(event_type)
+    .bind(payload)
+    .bind(now)
+    .bind(subscription_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Deletes events created before `before` along with their delivery logs, unless a
+/// delivery is still pending. Returns the number of events deleted.
+pub async fn prune_events(before: NaiveDateTime, pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM WebhookEvent e WHERE created_at < $1 AND NOT EXISTS (
+            SELECT 1 FROM WebhookDelivery d WHERE d.event_id = e.event_id AND d.status = 'pending'
+        )",
+    )
+    .bind(before)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
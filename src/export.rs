@@ -0,0 +1,428 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, QueryBuilder};
+use tokio_stream::StreamExt;
+use tracing::error;
+
+use crate::{
+    auth::{Authenticator, Viewer},
+    csv,
+};
+
+#[derive(Clone)]
+pub struct ExportState {
+    pub pool: Arc<PgPool>,
+    pub authenticator: Authenticator,
+}
+
+/// Routes streaming reports as CSV or NDJSON, for spreadsheets and scripts. Admins only.
+pub fn router(state: ExportState) -> Router {
+    Router::new()
+        .route("/export/members", get(export_members))
+        .route("/export/attendance", get(export_attendance))
+        .route(
+            "/export/attendance-summaries",
+            get(export_attendance_summaries),
+        )
+        .route("/export/streaks", get(export_streaks))
+        .with_state(state)
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+/// A row of an export, written either as a CSV line or a JSON object.
+trait ExportRow: for<'r> FromRow<'r, PgRow> + Serialize + Send + Unpin + 'static {
+    const COLUMNS: &'static [&'static str];
+
+    fn fields(&self) -> Vec<String>;
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+type HandlerError = (StatusCode, Json<serde_json::Value>);
+
+fn reject(status: StatusCode, message: &str) -> HandlerError {
+    (status, Json(json!({ "error": message })))
+}
+
+fn require_admin(state: &ExportState, headers: &HeaderMap) -> Result<(), HandlerError> {
+    match state.authenticator.viewer(headers) {
+        Viewer::Admin => Ok(()),
+        Viewer::Anonymous => Err(reject(StatusCode::UNAUTHORIZED, "Sign in required")),
+        Viewer::Member(_) => Err(reject(StatusCode::FORBIDDEN, "Only admins can export")),
+    }
+}
+
+/// Streams the rows of `query` as they are fetched, so large exports aren't held in memory.
+/// The connection is taken before responding, so a busy export pool is reported as such.
+async fn stream_rows<T: ExportRow>(
+    name: &str,
+    format: Format,
+    mut query: QueryBuilder<'static, Postgres>,
+    pool: Arc<PgPool>,
+) -> Result<Response, HandlerError> {
+    let mut connection = pool.acquire().await.map_err(|e| {
+        error!("Failed to get a connection for an export: {:?}", e);
+        reject(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Too many exports running, try again later",
+        )
+    })?;
+
+    let body = async_stream::stream! {
+        if let Format::Csv = format {
+            yield Ok(csv::write_record(T::COLUMNS));
+        }
+
+        let mut rows = query.build_query_as::<T>().fetch(&mut *connection);
+        while let Some(row) = rows.next().await {
+            match row {
+                Ok(row) => yield Ok(match format {
+                    Format::Csv => csv::write_record(row.fields()),
+                    Format::Ndjson => format!(
+                        "{}\n",
+                        serde_json::to_string(&row).expect("Export rows must serialize")
+                    ),
+                }),
+                Err(e) => {
+                    error!("Failed to export rows: {:?}", e);
+                    yield Err(e);
+                    break;
+                }
+            }
+        }
+    };
+
+    let (content_type, extension) = match format {
+        Format::Csv => ("text/csv; charset=utf-8", "csv"),
+        Format::Ndjson => ("application/x-ndjson", "ndjson"),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.{}\"", name, extension),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MembersFilter {
+    #[serde(default)]
+    format: Format,
+    year: Option<i32>,
+    group_id: Option<i32>,
+    /// Defaults to active members, like the `members` query. `all` exports everyone.
+    status: Option<String>,
+}
+
+#[derive(FromRow, Serialize)]
+struct MemberRow {
+    member_id: i32,
+    roll_no: String,
+    name: String,
+    email: String,
+    sex: String,
+    year: i32,
+    hostel: String,
+    mac_address: String,
+    discord_id: String,
+    group_id: i32,
+    status: String,
+    status_since: NaiveDate,
+}
+
+impl ExportRow for MemberRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "member_id",
+        "roll_no",
+        "name",
+        "email",
+        "sex",
+        "year",
+        "hostel",
+        "mac_address",
+        "discord_id",
+        "group_id",
+        "status",
+        "status_since",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.member_id.to_string(),
+            self.roll_no.clone(),
+            self.name.clone(),
+            self.email.clone(),
+            self.sex.clone(),
+            self.year.to_string(),
+            self.hostel.clone(),
+            self.mac_address.clone(),
+            self.discord_id.clone(),
+            self.group_id.to_string(),
+            self.status.clone(),
+            self.status_since.to_string(),
+        ]
+    }
+}
+
+async fn export_members(
+    State(state): State<ExportState>,
+    headers: HeaderMap,
+    Query(filter): Query<MembersFilter>,
+) -> Result<Response, HandlerError> {
+    require_admin(&state, &headers)?;
+
+    let mut query = QueryBuilder::new(
+        "SELECT member_id, roll_no, name, email, sex::text AS sex, year, hostel, mac_address,
+            discord_id, group_id, status::text AS status, status_since
+        FROM Member WHERE 1=1",
+    );
+    if let Some(year) = filter.year {
+        query.push(" AND year = ");
+        query.push_bind(year);
+    }
+    if let Some(group_id) = filter.group_id {
+        query.push(" AND group_id = ");
+        query.push_bind(group_id);
+    }
+    match filter.status.as_deref().map(str::to_ascii_lowercase) {
+        Some(status) if status == "all" => {}
+        status => {
+            query.push(" AND status::text = ");
+            query.push_bind(status.unwrap_or_else(|| "active".to_string()));
+        }
+    }
+    query.push(" ORDER BY roll_no");
+
+    stream_rows::<MemberRow>("members", filter.format, query, state.pool).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AttendanceFilter {
+    #[serde(default)]
+    format: Format,
+    from: NaiveDate,
+    to: NaiveDate,
+    member_id: Option<i32>,
+}
+
+#[derive(FromRow, Serialize)]
+struct AttendanceRow {
+    member_id: i32,
+    roll_no: String,
+    name: String,
+    date: NaiveDate,
+    is_present: bool,
+    time_in: Option<NaiveTime>,
+    time_out: Option<NaiveTime>,
+}
+
+impl ExportRow for AttendanceRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "member_id",
+        "roll_no",
+        "name",
+        "date",
+        "is_present",
+        "time_in",
+        "time_out",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.member_id.to_string(),
+            self.roll_no.clone(),
+            self.name.clone(),
+            self.date.to_string(),
+            self.is_present.to_string(),
+            optional(&self.time_in),
+            optional(&self.time_out),
+        ]
+    }
+}
+
+/// Attendance records from `from` to `to`, both inclusive.
+async fn export_attendance(
+    State(state): State<ExportState>,
+    headers: HeaderMap,
+    Query(filter): Query<AttendanceFilter>,
+) -> Result<Response, HandlerError> {
+    require_admin(&state, &headers)?;
+    if filter.from > filter.to {
+        return Err(reject(StatusCode::BAD_REQUEST, "from must not be after to"));
+    }
+
+    let mut query = QueryBuilder::new(
+        "SELECT a.member_id, m.roll_no, m.name, a.date, a.is_present, a.time_in, a.time_out
+        FROM Attendance a JOIN Member m ON m.member_id = a.member_id
+        WHERE a.date BETWEEN ",
+    );
+    query.push_bind(filter.from);
+    query.push(" AND ");
+    query.push_bind(filter.to);
+    if let Some(member_id) = filter.member_id {
+        query.push(" AND a.member_id = ");
+        query.push_bind(member_id);
+    }
+    query.push(" ORDER BY a.date, m.roll_no");
+
+    stream_rows::<AttendanceRow>("attendance", filter.format, query, state.pool).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryFilter {
+    #[serde(default)]
+    format: Format,
+    member_id: Option<i32>,
+    year: Option<i32>,
+    month: Option<i32>,
+}
+
+#[derive(FromRow, Serialize)]
+struct SummaryRow {
+    member_id: i32,
+    roll_no: String,
+    name: String,
+    year: i32,
+    month: i32,
+    days_attended: i32,
+}
+
+impl ExportRow for SummaryRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "member_id",
+        "roll_no",
+        "name",
+        "year",
+        "month",
+        "days_attended",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.member_id.to_string(),
+            self.roll_no.clone(),
+            self.name.clone(),
+            self.year.to_string(),
+            self.month.to_string(),
+            self.days_attended.to_string(),
+        ]
+    }
+}
+
+async fn export_attendance_summaries(
+    State(state): State<ExportState>,
+    headers: HeaderMap,
+    Query(filter): Query<SummaryFilter>,
+) -> Result<Response, HandlerError> {
+    require_admin(&state, &headers)?;
+
+    let mut query = QueryBuilder::new(
+        "SELECT s.member_id, m.roll_no, m.name, s.year, s.month, s.days_attended
+        FROM AttendanceSummary s JOIN Member m ON m.member_id = s.member_id
+        WHERE 1=1",
+    );
+    if let Some(member_id) = filter.member_id {
+        query.push(" AND s.member_id = ");
+        query.push_bind(member_id);
+    }
+    if let Some(year) = filter.year {
+        query.push(" AND s.year = ");
+        query.push_bind(year);
+    }
+    if let Some(month) = filter.month {
+        query.push(" AND s.month = ");
+        query.push_bind(month);
+    }
+    query.push(" ORDER BY s.year, s.month, m.roll_no");
+
+    stream_rows::<SummaryRow>("attendance-summaries", filter.format, query, state.pool).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StreakFilter {
+    #[serde(default)]
+    format: Format,
+    member_id: Option<i32>,
+}
+
+#[derive(FromRow, Serialize)]
+struct StreakRow {
+    member_id: i32,
+    roll_no: String,
+    name: String,
+    current_streak: i32,
+    max_streak: i32,
+    missed_streak: i32,
+}
+
+impl ExportRow for StreakRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "member_id",
+        "roll_no",
+        "name",
+        "current_streak",
+        "max_streak",
+        "missed_streak",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.member_id.to_string(),
+            self.roll_no.clone(),
+            self.name.clone(),
+            self.current_streak.to_string(),
+            self.max_streak.to_string(),
+            self.missed_streak.to_string(),
+        ]
+    }
+}
+
+async fn export_streaks(
+    State(state): State<ExportState>,
+    headers: HeaderMap,
+    Query(filter): Query<StreakFilter>,
+) -> Result<Response, HandlerError> {
+    require_admin(&state, &headers)?;
+
+    let mut query = QueryBuilder::new(
+        "SELECT s.member_id, m.roll_no, m.name, s.current_streak, s.max_streak, s.missed_streak
+        FROM StatusUpdateStreak s JOIN Member m ON m.member_id = s.member_id
+        WHERE 1=1",
+    );
+    if let Some(member_id) = filter.member_id {
+        query.push(" AND s.member_id = ");
+        query.push_bind(member_id);
+    }
+    query.push(" ORDER BY m.roll_no");
+
+    stream_rows::<StreakRow>("streaks", filter.format, query, state.pool).await
+}
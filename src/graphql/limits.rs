@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextValidation},
+    ErrorExtensionValues, ServerError, ValidationResult,
+};
+
+/// Upper bounds for incoming queries, enforced by async-graphql during validation.
+#[derive(Clone, Copy)]
+pub struct QueryLimits {
+    pub max_depth: usize,
+    pub max_complexity: usize,
+}
+
+/// async-graphql reports limit violations as plain messages. This extension tags
+/// them with a `code` and the configured limit so clients can tell them apart.
+pub struct LimitErrorCodes(pub QueryLimits);
+
+impl ExtensionFactory for LimitErrorCodes {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(LimitErrorCodesExtension(self.0))
+    }
+}
+
+struct LimitErrorCodesExtension(QueryLimits);
+
+#[async_trait::async_trait]
+impl Extension for LimitErrorCodesExtension {
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        next.run(ctx).await.map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|mut error| {
+                    let tag = match error.message.as_str() {
+                        "Query is nested too deep." => {
+                            Some(("DEPTH_LIMIT_EXCEEDED", self.0.max_depth))
+                        }
+                        "Query is too complex." => {
+                            Some(("COMPLEXITY_LIMIT_EXCEEDED", self.0.max_complexity))
+                        }
+                        _ => None,
+                    };
+
+                    if let Some((code, limit)) = tag {
+                        let extensions = error
+                            .extensions
+                            .get_or_insert_with(ErrorExtensionValues::default);
+                        extensions.set("code", code);
+                        extensions.set("limit", limit as u64);
+                    }
+                    error
+                })
+                .collect()
+        })
+    }
+}
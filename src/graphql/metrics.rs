@@ -0,0 +1,244 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use async_graphql::{
+    extensions::{
+        Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextPrepareRequest,
+        NextRequest,
+    },
+    parser::types::{
+        DocumentOperations, ExecutableDocument, OperationDefinition, OperationType, Selection,
+        SelectionSet,
+    },
+    Request, Response, ServerResult, Value, Variables,
+};
+
+use crate::metrics::Metrics;
+
+/// Fields every root type has besides its own.
+const INTROSPECTION_FIELDS: [&str; 3] = ["__schema", "__type", "__typename"];
+
+/// Counts and times every GraphQL request by the root fields it selects, and counts the errors
+/// in responses by their `code`. Only fields of the schema are used as labels, never text chosen
+/// by clients, so the number of series stays bounded.
+pub struct OperationMetrics(pub Metrics);
+
+impl ExtensionFactory for OperationMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(OperationMetricsExtension {
+            metrics: self.0.clone(),
+            operation_name: Mutex::new(None),
+            root_fields: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+/// Created for each request, so it can remember what the request selected.
+struct OperationMetricsExtension {
+    metrics: Metrics,
+    operation_name: Mutex<Option<String>>,
+    root_fields: Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for OperationMetricsExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let started = Instant::now();
+        let response = next.run(ctx).await;
+
+        let root_fields =
+            std::mem::take(&mut *self.root_fields.lock().unwrap_or_else(|e| e.into_inner()));
+        let codes = response.errors.iter().map(|error| {
+            match error
+                .extensions
+                .as_ref()
+                .and_then(|extensions| extensions.get("code"))
+            {
+                Some(Value::String(code)) => Some(code.as_str()),
+                _ => None,
+            }
+        });
+        self.metrics
+            .record_graphql_request(&root_fields, started.elapsed(), codes);
+
+        response
+    }
+
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        *self
+            .operation_name
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = request.operation_name.clone();
+        next.run(ctx, request).await
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await;
+
+        let requested = self
+            .operation_name
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Ok(document) = &document {
+            // A document with a single operation can be sent without `operationName`.
+            let operation = match &document.operations {
+                DocumentOperations::Single(operation) => Some(operation),
+                DocumentOperations::Multiple(operations) => match &requested {
+                    Some(name) => operations.get(name.as_str()),
+                    None if operations.len() == 1 => operations.values().next(),
+                    None => None,
+                },
+            };
+            if let Some(operation) = operation {
+                *self.root_fields.lock().unwrap_or_else(|e| e.into_inner()) =
+                    root_fields(ctx, document, &operation.node);
+            }
+        }
+
+        document
+    }
+}
+
+/// Names of the root fields `operation` selects that exist in the schema, sorted.
+fn root_fields(
+    ctx: &ExtensionContext<'_>,
+    document: &ExecutableDocument,
+    operation: &OperationDefinition,
+) -> Vec<String> {
+    let registry = &ctx.schema_env.registry;
+    let root_type = match operation.ty {
+        OperationType::Query => Some(&registry.query_type),
+        OperationType::Mutation => registry.mutation_type.as_ref(),
+        OperationType::Subscription => registry.subscription_type.as_ref(),
+    }
+    .and_then(|name| registry.types.get(name));
+
+    let mut selected = BTreeSet::new();
+    collect_fields(
+        &operation.selection_set.node,
+        document,
+        &mut HashSet::new(),
+        &mut selected,
+    );
+
+    selected
+        .into_iter()
+        .filter(|name| {
+            INTROSPECTION_FIELDS.contains(name)
+                || root_type.is_some_and(|root| root.field_by_name(name).is_some())
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Adds the names of the fields in `selection_set` to `fields`, following fragments.
+/// `visited` guards against fragments spreading each other, which validation rejects later.
+fn collect_fields<'a>(
+    selection_set: &'a SelectionSet,
+    document: &'a ExecutableDocument,
+    visited: &mut HashSet<&'a str>,
+    fields: &mut BTreeSet<&'a str>,
+) {
+    for selection in &selection_set.items {
+        match &selection.node {
+            Selection::Field(field) => {
+                fields.insert(field.node.name.node.as_str());
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = spread.node.fragment_name.node.as_str();
+                if let Some(fragment) = document.fragments.get(name) {
+                    if visited.insert(name) {
+                        collect_fields(
+                            &fragment.node.selection_set.node,
+                            document,
+                            visited,
+                            fields,
+                        );
+                    }
+                }
+            }
+            Selection::InlineFragment(fragment) => {
+                collect_fields(&fragment.node.selection_set.node, document, visited, fields);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+    use super::*;
+
+    struct TestQuery;
+
+    #[Object]
+    impl TestQuery {
+        async fn members(&self) -> i32 {
+            1
+        }
+
+        async fn projects(&self) -> i32 {
+            2
+        }
+    }
+
+    fn schema(metrics: &Metrics) -> Schema<TestQuery, EmptyMutation, EmptySubscription> {
+        Schema::build(TestQuery, EmptyMutation, EmptySubscription)
+            .extension(OperationMetrics(metrics.clone()))
+            .finish()
+    }
+
+    #[tokio::test]
+    async fn labels_requests_by_root_field() {
+        let metrics = Metrics::new();
+        let schema = schema(&metrics);
+
+        schema.execute("query Whatever { members projects }").await;
+        schema
+            .execute("query { ...Fields } fragment Fields on Query { m: members }")
+            .await;
+
+        assert_eq!(metrics.graphql_requests("members"), 2);
+        assert_eq!(metrics.graphql_requests("projects"), 1);
+        assert_eq!(metrics.graphql_requests("Whatever"), 0);
+        assert_eq!(metrics.graphql_requests("m"), 0);
+    }
+
+    #[tokio::test]
+    async fn ignores_names_chosen_by_clients() {
+        let metrics = Metrics::new();
+        let schema = schema(&metrics);
+
+        for i in 0..300 {
+            schema
+                .execute(format!("query Junk{} {{ members }}", i))
+                .await;
+        }
+        schema.execute("{ junk }").await;
+        schema.execute("{").await;
+        schema
+            .execute(Request::new("query A { members } query B { projects }").operation_name("B"))
+            .await;
+
+        assert_eq!(metrics.graphql_requests("members"), 300);
+        assert_eq!(metrics.graphql_requests("projects"), 1);
+        assert_eq!(metrics.graphql_requests("junk"), 0);
+        assert_eq!(metrics.graphql_requests("other"), 2);
+    }
+}
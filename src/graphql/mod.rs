@@ -1,7 +1,17 @@
 use async_graphql::MergedObject;
-use mutations::{AttendanceMutations, MemberMutations, ProjectMutations, StreakMutations};
-use queries::{AttendanceQueries, MemberQueries, ProjectQueries, StreakQueries};
+use mutations::{
+    AttendanceMutations, ContributionMutations, DigestMutations, DiscordMutations, GroupMutations,
+    MemberMutations, ProfileMutations, ProjectMutations, ReportMutations, RolloverMutations,
+    StatusUpdateMutations, StreakMutations, WebhookMutations,
+};
+use queries::{
+    AttendanceQueries, ContributionQueries, DigestQueries, DiscordQueries, GroupQueries,
+    LeaderboardQueries, MemberQueries, ProfileQueries, ProjectQueries, ReportQueries,
+    RolloverQueries, StreakQueries, WebhookQueries,
+};
 
+pub mod limits;
+pub mod metrics;
 pub mod mutations;
 pub mod queries;
 
@@ -11,6 +21,15 @@ pub struct Query(
     AttendanceQueries,
     StreakQueries,
     ProjectQueries,
+    LeaderboardQueries,
+    ContributionQueries,
+    GroupQueries,
+    RolloverQueries,
+    ProfileQueries,
+    ReportQueries,
+    WebhookQueries,
+    DiscordQueries,
+    DigestQueries,
 );
 
 #[derive(MergedObject, Default)]
@@ -19,4 +38,13 @@ pub struct Mutation(
     AttendanceMutations,
     StreakMutations,
     ProjectMutations,
+    StatusUpdateMutations,
+    ContributionMutations,
+    GroupMutations,
+    RolloverMutations,
+    ProfileMutations,
+    ReportMutations,
+    WebhookMutations,
+    DiscordMutations,
+    DigestMutations,
 );
@@ -1,13 +1,17 @@
 use std::sync::Arc;
 
 use async_graphql::{Context, Object, Result};
-use chrono::Local;
-use chrono_tz::Asia::Kolkata;
+use chrono::{Local, Timelike};
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
 use sqlx::PgPool;
 
-use crate::models::attendance::{Attendance, MarkAttendanceInput};
+use crate::{
+    config::timezone,
+    events::{emit, Event},
+    metrics::{HmacCheck, Metrics},
+    models::attendance::{Attendance, MarkAttendanceInput},
+};
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -29,6 +33,7 @@ impl AttendanceMutations {
         let secret_key = ctx
             .data::<String>()
             .expect("ROOT_SECRET must be found in context");
+        let metrics = ctx.data::<Metrics>().expect("Metrics must be in context.");
 
         let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes())
             .expect("HMAC can take key of any size");
@@ -36,13 +41,17 @@ impl AttendanceMutations {
         mac.update(message.as_bytes());
 
         let expected_signature = mac.finalize().into_bytes();
-        let received_signature = hex::decode(input.hmac_signature)?;
+        let received_signature = hex::decode(input.hmac_signature).inspect_err(|_| {
+            metrics.record_hmac_failure(HmacCheck::Attendance);
+        })?;
 
         if expected_signature.as_slice() != received_signature.as_slice() {
+            metrics.record_hmac_failure(HmacCheck::Attendance);
             return Err(async_graphql::Error::new("HMAC verification failed"));
         }
 
-        let now = Local::now().with_timezone(&Kolkata).time();
+        let now = Local::now().with_timezone(&timezone()).time();
+        let mut tx = pool.begin().await?;
         let attendance = sqlx::query_as::<_, Attendance>(
             "UPDATE Attendance SET time_in = CASE 
                 WHEN time_in IS NULL THEN $1 
@@ -55,9 +64,13 @@ impl AttendanceMutations {
         .bind(now)
         .bind(input.member_id)
         .bind(input.date)
-        .fetch_one(pool.as_ref())
+        .fetch_one(&mut *tx)
         .await?;
 
+        emit(Event::AttendanceMarked(&attendance), &mut *tx).await?;
+        tx.commit().await?;
+        metrics.record_attendance_mark(now.hour());
+
         Ok(attendance)
     }
 }
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use sqlx::PgPool;
+
+use crate::{
+    auth::{require_admin, require_member_or_admin, Viewer},
+    models::contribution::{
+        CreateForgeWebhookInput, ForgeAccount, ForgeAccountInput, ForgeWebhook,
+    },
+};
+
+#[derive(Default)]
+pub struct ContributionMutations;
+
+#[Object]
+impl ContributionMutations {
+    /// Links a member to their account on a forge so their contributions can be credited.
+    /// Replaces any account the member already linked on the same host. Members can only
+    /// link their own accounts, which an admin then has to approve. Accounts linked by
+    /// admins are approved right away.
+    #[graphql(name = "linkForgeAccount")]
+    async fn link_forge_account(
+        &self,
+        ctx: &Context<'_>,
+        input: ForgeAccountInput,
+    ) -> Result<ForgeAccount> {
+        require_member_or_admin(ctx, input.member_id)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let is_admin = matches!(ctx.data::<Viewer>(), Ok(Viewer::Admin));
+
+        // Linking the same username again keeps its approval.
+        let account = sqlx::query_as::<_, ForgeAccount>(
+            "INSERT INTO ForgeAccount (member_id, host, username, approved) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (member_id, host) DO UPDATE SET
+                username = EXCLUDED.username,
+                approved = EXCLUDED.approved
+                    OR (ForgeAccount.approved AND ForgeAccount.username = EXCLUDED.username)
+            RETURNING *",
+        )
+        .bind(input.member_id)
+        .bind(input.host.trim().to_lowercase())
+        .bind(input.username.trim())
+        .bind(is_admin)
+        .fetch_one(pool.as_ref())
+        .await?;
+
+        Ok(account)
+    }
+
+    /// Confirms that a linked account belongs to the member, so their contributions are credited.
+    #[graphql(name = "approveForgeAccount")]
+    async fn approve_forge_account(
+        &self,
+        ctx: &Context<'_>,
+        member_id: i32,
+        host: String,
+    ) -> Result<ForgeAccount> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let account = sqlx::query_as::<_, ForgeAccount>(
+            "UPDATE ForgeAccount SET approved = TRUE WHERE member_id = $1 AND host = $2 RETURNING *",
+        )
+        .bind(member_id)
+        .bind(host.trim().to_lowercase())
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Forge account not found"))?;
+
+        Ok(account)
+    }
+
+    #[graphql(name = "unlinkForgeAccount")]
+    async fn unlink_forge_account(
+        &self,
+        ctx: &Context<'_>,
+        member_id: i32,
+        host: String,
+    ) -> Result<ForgeAccount> {
+        require_member_or_admin(ctx, member_id)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let account = sqlx::query_as::<_, ForgeAccount>(
+            "DELETE FROM ForgeAccount WHERE member_id = $1 AND host = $2 RETURNING *",
+        )
+        .bind(member_id)
+        .bind(host.trim().to_lowercase())
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Forge account not found"))?;
+
+        Ok(account)
+    }
+
+    /// Registers a webhook. Point the forge at `/webhooks/forge/<hookId>` using the same secret.
+    #[graphql(name = "createForgeWebhook")]
+    async fn create_forge_webhook(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateForgeWebhookInput,
+    ) -> Result<ForgeWebhook> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let hook = sqlx::query_as::<_, ForgeWebhook>(
+            "INSERT INTO ForgeWebhook (project_id, secret, counts_as_status_update)
+            VALUES ($1, $2, $3) RETURNING *",
+        )
+        .bind(input.project_id)
+        .bind(&input.secret)
+        .bind(input.counts_as_status_update)
+        .fetch_one(pool.as_ref())
+        .await?;
+
+        Ok(hook)
+    }
+
+    #[graphql(name = "deleteForgeWebhook")]
+    async fn delete_forge_webhook(&self, ctx: &Context<'_>, hook_id: i32) -> Result<ForgeWebhook> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let hook = sqlx::query_as::<_, ForgeWebhook>(
+            "DELETE FROM ForgeWebhook WHERE hook_id = $1 RETURNING *",
+        )
+        .bind(hook_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Webhook not found"))?;
+
+        Ok(hook)
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use chrono::{Duration, Local, NaiveDate};
+use sqlx::PgPool;
+
+use crate::{
+    auth::{require_admin, signed_in_member, Viewer},
+    config::timezone,
+    email::{digest::Digests, opt_out},
+    streaks::StreakRules,
+};
+
+#[derive(Default)]
+pub struct DigestMutations;
+
+#[Object]
+impl DigestMutations {
+    /// Emails the digest of a group's week ending on `to`, yesterday by default, to its mentor.
+    /// Returns false if nothing was sent because the group has no mentor or the mentor opted
+    /// out. Admins only.
+    #[graphql(name = "sendGroupDigest")]
+    async fn send_group_digest(
+        &self,
+        ctx: &Context<'_>,
+        group_id: i32,
+        to: Option<NaiveDate>,
+    ) -> Result<bool> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let digests = ctx.data::<Digests>().expect("Digests must be in context.");
+        let streak_rules = ctx
+            .data::<StreakRules>()
+            .expect("StreakRules must be in context.");
+
+        if !digests.is_enabled() {
+            return Err("Email is not configured".into());
+        }
+        let to = to.unwrap_or_else(|| {
+            Local::now().with_timezone(&timezone()).date_naive() - Duration::days(1)
+        });
+        let digest = digests
+            .render(group_id, to, streak_rules, pool.as_ref())
+            .await?
+            .ok_or("Group not found")?;
+
+        Ok(digests.send(&digest, pool.as_ref()).await?)
+    }
+
+    /// Opts in to or out of weekly digests. Members can change their own subscription, while
+    /// admins pass `memberId` to change anyone's.
+    #[graphql(name = "setDigestSubscription")]
+    async fn set_digest_subscription(
+        &self,
+        ctx: &Context<'_>,
+        subscribed: bool,
+        member_id: Option<i32>,
+    ) -> Result<bool> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let viewer = ctx.data::<Viewer>().expect("Viewer must be in context.");
+        let member_id = match member_id {
+            Some(member_id) if *viewer == Viewer::Member(member_id) => member_id,
+            Some(member_id) => {
+                require_admin(ctx)?;
+                member_id
+            }
+            None => signed_in_member(ctx)?,
+        };
+
+        if subscribed {
+            sqlx::query("DELETE FROM DigestOptOut WHERE member_id = $1")
+                .bind(member_id)
+                .execute(pool.as_ref())
+                .await?;
+        } else {
+            let exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM Member WHERE member_id = $1)",
+            )
+            .bind(member_id)
+            .fetch_one(pool.as_ref())
+            .await?;
+            if !exists {
+                return Err("Member not found".into());
+            }
+            opt_out(member_id, pool.as_ref()).await?;
+        }
+
+        Ok(subscribed)
+    }
+}
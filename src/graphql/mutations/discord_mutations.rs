@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use chrono::{Local, NaiveDate};
+use sqlx::PgPool;
+
+use crate::{
+    auth::require_admin,
+    config::timezone,
+    models::discord::{
+        CreateDiscordChannelInput, DiscordChannel, DiscordRoute, NotificationKind,
+        SetDiscordRouteInput,
+    },
+    notifier::Notifier,
+};
+
+#[derive(Default)]
+pub struct DiscordMutations;
+
+#[Object]
+impl DiscordMutations {
+    /// Admins only.
+    #[graphql(name = "createDiscordChannel")]
+    async fn create_discord_channel(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateDiscordChannelInput,
+    ) -> Result<DiscordChannel> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        let channel = sqlx::query_as::<_, DiscordChannel>(
+            "INSERT INTO DiscordChannel (name, webhook_url, created_at)
+            VALUES ($1, $2, $3) RETURNING *",
+        )
+        .bind(input.name.trim())
+        .bind(&input.webhook_url)
+        .bind(now)
+        .fetch_one(pool.as_ref())
+        .await?;
+
+        Ok(channel)
+    }
+
+    /// Deletes a channel along with its routes. Admins only.
+    #[graphql(name = "deleteDiscordChannel")]
+    async fn delete_discord_channel(
+        &self,
+        ctx: &Context<'_>,
+        channel_id: i32,
+    ) -> Result<DiscordChannel> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let channel = sqlx::query_as::<_, DiscordChannel>(
+            "DELETE FROM DiscordChannel WHERE channel_id = $1 RETURNING *",
+        )
+        .bind(channel_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Channel not found"))?;
+
+        Ok(channel)
+    }
+
+    /// Sends a kind of notification to a channel, replacing its template if it already
+    /// receives it. Admins only.
+    #[graphql(name = "setDiscordRoute")]
+    async fn set_discord_route(
+        &self,
+        ctx: &Context<'_>,
+        input: SetDiscordRouteInput,
+    ) -> Result<DiscordRoute> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let notifier = ctx
+            .data::<Notifier>()
+            .expect("Notifier must be in context.");
+
+        if let Some(template) = &input.template {
+            notifier.validate_template(template)?;
+        }
+
+        let route = sqlx::query_as::<_, DiscordRoute>(
+            "INSERT INTO DiscordRoute (channel_id, kind, template) VALUES ($1, $2, $3)
+            ON CONFLICT (channel_id, kind) DO UPDATE SET template = EXCLUDED.template
+            RETURNING *",
+        )
+        .bind(input.channel_id)
+        .bind(input.kind)
+        .bind(&input.template)
+        .fetch_one(pool.as_ref())
+        .await?;
+
+        Ok(route)
+    }
+
+    /// Admins only.
+    #[graphql(name = "removeDiscordRoute")]
+    async fn remove_discord_route(
+        &self,
+        ctx: &Context<'_>,
+        channel_id: i32,
+        kind: NotificationKind,
+    ) -> Result<DiscordRoute> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let route = sqlx::query_as::<_, DiscordRoute>(
+            "DELETE FROM DiscordRoute WHERE channel_id = $1 AND kind = $2 RETURNING *",
+        )
+        .bind(channel_id)
+        .bind(kind)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Route not found"))?;
+
+        Ok(route)
+    }
+
+    /// Posts a notification about `date` to its channels right away, e.g. to resend one
+    /// the daily task failed to post. Returns the number of messages posted. Admins only.
+    #[graphql(name = "sendDiscordNotification")]
+    async fn send_discord_notification(
+        &self,
+        ctx: &Context<'_>,
+        kind: NotificationKind,
+        date: NaiveDate,
+    ) -> Result<i32> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let notifier = ctx
+            .data::<Notifier>()
+            .expect("Notifier must be in context.");
+
+        Ok(notifier.notify(kind, date, pool.as_ref()).await?)
+    }
+}
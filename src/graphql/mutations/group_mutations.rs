@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use sqlx::{PgPool, QueryBuilder};
+
+use super::partial_update::{set, set_nullable};
+use crate::{
+    auth::require_admin,
+    models::group::{CreateGroupInput, Group, UpdateGroupInput},
+};
+
+#[derive(Default)]
+pub struct GroupMutations;
+
+#[Object]
+impl GroupMutations {
+    #[graphql(name = "createGroup")]
+    async fn create_group(&self, ctx: &Context<'_>, input: CreateGroupInput) -> Result<Group> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let group = sqlx::query_as::<_, Group>(
+            "INSERT INTO MemberGroup (name, description, mentor_id, meeting_slot)
+            VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(&input.name)
+        .bind(&input.description)
+        .bind(input.mentor_id)
+        .bind(&input.meeting_slot)
+        .fetch_one(pool.as_ref())
+        .await?;
+
+        Ok(group)
+    }
+
+    #[graphql(name = "updateGroup")]
+    async fn update_group(&self, ctx: &Context<'_>, input: UpdateGroupInput) -> Result<Group> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let mut query = QueryBuilder::new("UPDATE MemberGroup SET group_id = group_id");
+        set(&mut query, "name", input.name);
+        set_nullable(&mut query, "description", input.description);
+        set_nullable(&mut query, "mentor_id", input.mentor_id);
+        set_nullable(&mut query, "meeting_slot", input.meeting_slot);
+        query.push(" WHERE group_id = ");
+        query.push_bind(input.group_id);
+        query.push(" RETURNING *");
+
+        let group = query
+            .build_query_as::<Group>()
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Group not found"))?;
+
+        Ok(group)
+    }
+
+    /// Only empty groups can be deleted. Returns the deleted group.
+    #[graphql(name = "deleteGroup")]
+    async fn delete_group(&self, ctx: &Context<'_>, group_id: i32) -> Result<Group> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let member_count =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM Member WHERE group_id = $1")
+                .bind(group_id)
+                .fetch_one(pool.as_ref())
+                .await?;
+        if member_count > 0 {
+            return Err(async_graphql::Error::new(format!(
+                "Group still has {} members",
+                member_count
+            )));
+        }
+
+        let group =
+            sqlx::query_as::<_, Group>("DELETE FROM MemberGroup WHERE group_id = $1 RETURNING *")
+                .bind(group_id)
+                .fetch_optional(pool.as_ref())
+                .await?
+                .ok_or_else(|| async_graphql::Error::new("Group not found"))?;
+
+        Ok(group)
+    }
+}
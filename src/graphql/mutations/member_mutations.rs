@@ -1,11 +1,21 @@
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result};
+use std::io::Read;
+
+use async_graphql::{Context, Object, Result, Upload};
 use chrono::Local;
-use chrono_tz::Asia::Kolkata;
 use sqlx::PgPool;
 
-use crate::models::member::{CreateMemberInput, Member};
+use crate::{
+    auth::require_admin,
+    config::timezone,
+    lifecycle::{apply_due_status_changes, import::import_members, insert_member},
+    models::{
+        member::{CreateMemberInput, Member, MemberStatusChange, SetMemberStatusInput},
+        member_import::MemberImportReport,
+    },
+    validation::ValidationRules,
+};
 
 #[derive(Default)]
 pub struct MemberMutations;
@@ -14,26 +24,95 @@ pub struct MemberMutations;
 impl MemberMutations {
     #[graphql(name = "createMember")]
     async fn create_member(&self, ctx: &Context<'_>, input: CreateMemberInput) -> Result<Member> {
+        require_admin(ctx)?;
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let rules = ctx
+            .data::<ValidationRules>()
+            .expect("ValidationRules must be in context.");
+        let input = input.validate(rules)?;
+
+        let now = Local::now().with_timezone(&timezone()).date_naive();
+        let mut tx = pool.begin().await?;
+        let member = insert_member(&input, now, &mut tx).await?;
+        tx.commit().await?;
+
+        Ok(member)
+    }
 
-        let now = Local::now().with_timezone(&Kolkata).date_naive();
-        let member = sqlx::query_as::<_, Member>(
-            "INSERT INTO Member (roll_no, name, email, sex, year, hostel, mac_address, discord_id, group_id, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *"
+    /// Creates members from a CSV file with a header row naming the `CreateMemberInput`
+    /// fields. Nothing is imported unless every row is valid. Admins only.
+    #[graphql(name = "importMembers")]
+    async fn import_members(
+        &self,
+        ctx: &Context<'_>,
+        file: Upload,
+        #[graphql(default = false)] dry_run: bool,
+    ) -> Result<MemberImportReport> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let rules = ctx
+            .data::<ValidationRules>()
+            .expect("ValidationRules must be in context.");
+
+        let mut csv = String::new();
+        file.value(ctx)?.content.read_to_string(&mut csv)?;
+
+        let today = Local::now().with_timezone(&timezone()).date_naive();
+        Ok(import_members(&csv, rules, today, dry_run, pool.as_ref()).await?)
+    }
+
+    /// Moves a member to a new status, either right away or on a future date. Admins only.
+    #[graphql(name = "setMemberStatus")]
+    async fn set_member_status(
+        &self,
+        ctx: &Context<'_>,
+        input: SetMemberStatusInput,
+    ) -> Result<MemberStatusChange> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let today = Local::now().with_timezone(&timezone()).date_naive();
+        let effective_from = input.effective_from.unwrap_or(today);
+
+        let member = sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE member_id = $1")
+            .bind(input.member_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Member not found"))?;
+
+        // Backdating past the current status would leave `status_since` moving backwards.
+        if effective_from < member.status_since {
+            return Err(async_graphql::Error::new(format!(
+                "Member has been {:?} since {}, the change can't take effect before that",
+                member.status, member.status_since
+            )));
+        }
+        if effective_from <= today && member.status == input.status {
+            return Err(async_graphql::Error::new(format!(
+                "Member is already {:?}",
+                input.status
+            )));
+        }
+
+        let change = sqlx::query_as::<_, MemberStatusChange>(
+            "INSERT INTO MemberStatusChange (member_id, status, effective_from, reason)
+            VALUES ($1, $2, $3, $4) RETURNING *",
         )
-        .bind(&input.roll_no)
-        .bind(&input.name)
-        .bind(&input.email)
-        .bind(input.sex)
-        .bind(input.year)
-        .bind(&input.hostel)
-        .bind(&input.mac_address)
-        .bind(&input.discord_id)
-        .bind(input.group_id)
-        .bind(now)
+        .bind(input.member_id)
+        .bind(input.status)
+        .bind(effective_from)
+        .bind(&input.reason)
         .fetch_one(pool.as_ref())
         .await?;
 
-        Ok(member)
+        if effective_from > today {
+            return Ok(change);
+        }
+
+        apply_due_status_changes(today, pool.as_ref()).await?;
+        Ok(MemberStatusChange {
+            applied: true,
+            ..change
+        })
     }
 }
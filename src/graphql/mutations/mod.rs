@@ -1,9 +1,28 @@
 pub mod attendance_mutations;
+pub mod contribution_mutations;
+pub mod digest_mutations;
+pub mod discord_mutations;
+pub mod group_mutations;
 pub mod member_mutations;
+mod partial_update;
+pub mod profile_mutations;
 pub mod project_mutations;
+pub mod report_mutations;
+pub mod rollover_mutations;
+pub mod status_update_mutations;
 pub mod streak_mutations;
+pub mod webhook_mutations;
 
 pub use attendance_mutations::AttendanceMutations;
+pub use contribution_mutations::ContributionMutations;
+pub use digest_mutations::DigestMutations;
+pub use discord_mutations::DiscordMutations;
+pub use group_mutations::GroupMutations;
 pub use member_mutations::MemberMutations;
+pub use profile_mutations::ProfileMutations;
 pub use project_mutations::ProjectMutations;
+pub use report_mutations::ReportMutations;
+pub use rollover_mutations::RolloverMutations;
+pub use status_update_mutations::StatusUpdateMutations;
 pub use streak_mutations::StreakMutations;
+pub use webhook_mutations::WebhookMutations;
@@ -0,0 +1,33 @@
+use async_graphql::MaybeUndefined;
+use sqlx::{Postgres, QueryBuilder};
+
+/// Appends `, <column> = <value>` to an `UPDATE ... SET` if the field was given.
+pub(super) fn set<'a, T>(query: &mut QueryBuilder<'a, Postgres>, column: &str, value: Option<T>)
+where
+    T: 'a + sqlx::Encode<'a, Postgres> + sqlx::Type<Postgres> + Send,
+{
+    if let Some(value) = value {
+        query.push(format!(", {} = ", column));
+        query.push_bind(value);
+    }
+}
+
+/// Like `set`, but an explicit `null` clears the column.
+pub(super) fn set_nullable<'a, T>(
+    query: &mut QueryBuilder<'a, Postgres>,
+    column: &str,
+    value: MaybeUndefined<T>,
+) where
+    T: 'a + sqlx::Encode<'a, Postgres> + sqlx::Type<Postgres> + Send,
+{
+    match value {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => {
+            query.push(format!(", {} = NULL", column));
+        }
+        MaybeUndefined::Value(value) => {
+            query.push(format!(", {} = ", column));
+            query.push_bind(value);
+        }
+    }
+}
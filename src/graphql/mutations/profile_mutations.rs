@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use chrono::Local;
+use sqlx::{PgPool, QueryBuilder};
+
+use super::partial_update::{set, set_nullable};
+use crate::{
+    auth::signed_in_member,
+    config::timezone,
+    models::profile::{MemberProfile, UpdateProfileInput},
+    validation::ValidationRules,
+};
+
+#[derive(Default)]
+pub struct ProfileMutations;
+
+#[Object]
+impl ProfileMutations {
+    /// Edits the profile of the signed in member, creating it on first use.
+    #[graphql(name = "updateProfile")]
+    async fn update_profile(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateProfileInput,
+    ) -> Result<MemberProfile> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let rules = ctx
+            .data::<ValidationRules>()
+            .expect("ValidationRules must be in context.");
+        let member_id = signed_in_member(ctx)?;
+        let input = input.validate(rules)?;
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("INSERT INTO MemberProfile (member_id) VALUES ($1) ON CONFLICT DO NOTHING")
+            .bind(member_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        let mut query = QueryBuilder::new("UPDATE MemberProfile SET updated_at = ");
+        query.push_bind(now);
+
+        set_nullable(&mut query, "bio", input.bio);
+        set_nullable(&mut query, "pronouns", input.pronouns);
+        set_nullable(&mut query, "avatar_url", input.avatar_url);
+        set_nullable(&mut query, "website_url", input.website_url);
+        set_nullable(&mut query, "github_handle", input.github_handle);
+        set_nullable(&mut query, "gitlab_handle", input.gitlab_handle);
+        set_nullable(&mut query, "linkedin_handle", input.linkedin_handle);
+        set(&mut query, "skills", input.skills);
+        set(&mut query, "interests", input.interests);
+        set(&mut query, "is_public", input.is_public);
+        set(&mut query, "show_socials", input.show_socials);
+        set(&mut query, "show_email", input.show_email);
+
+        query.push(" WHERE member_id = ");
+        query.push_bind(member_id);
+        query.push(" RETURNING *");
+
+        let profile = query
+            .build_query_as::<MemberProfile>()
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(profile)
+    }
+}
@@ -1,28 +1,255 @@
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result};
-use sqlx::PgPool;
+use async_graphql::{Context, Error, ErrorExtensions, Object, Result};
+use chrono::NaiveDate;
+use sqlx::{PgExecutor, PgPool, QueryBuilder};
 
-use crate::models::project::{Project, SetProjectInput};
+use super::partial_update::{set, set_nullable};
+use crate::{
+    auth::{require_admin, require_member_or_admin, signed_in_member, Viewer},
+    events::{emit, Event},
+    models::{
+        project::{
+            Project, ProjectMember, ProjectMemberInput, ProjectRole, ProjectStatus,
+            SetProjectInput, UpdateProjectInput,
+        },
+        project_activity::ProjectActivity,
+    },
+    project_activity::{import_project_activity, ForgeClients},
+};
 
 #[derive(Default)]
 pub struct ProjectMutations;
 
+/// Fails unless the request comes from one of the project's leads or an admin tool.
+async fn require_lead_or_admin(ctx: &Context<'_>, project_id: i32, pool: &PgPool) -> Result<()> {
+    if let Viewer::Admin = ctx.data::<Viewer>().expect("Viewer must be in context.") {
+        return Ok(());
+    }
+    let member_id = signed_in_member(ctx)?;
+
+    let is_lead = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (
+            SELECT 1 FROM ProjectMember WHERE project_id = $1 AND member_id = $2 AND role = 'lead'
+        )",
+    )
+    .bind(project_id)
+    .bind(member_id)
+    .fetch_one(pool)
+    .await?;
+
+    if is_lead {
+        Ok(())
+    } else {
+        Err(
+            Error::new("Only the project's leads and admins can do this")
+                .extend_with(|_, ext| ext.set("code", "FORBIDDEN")),
+        )
+    }
+}
+
+/// Fails if a change to a project's members left it without a lead.
+async fn require_lead_left<'e>(project_id: i32, executor: impl PgExecutor<'e>) -> Result<()> {
+    let has_lead = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM ProjectMember WHERE project_id = $1 AND role = 'lead')",
+    )
+    .bind(project_id)
+    .fetch_one(executor)
+    .await?;
+
+    if has_lead {
+        Ok(())
+    } else {
+        Err(Error::new(
+            "A project must keep a lead, make someone else lead first",
+        ))
+    }
+}
+
 #[Object]
 impl ProjectMutations {
+    /// Members create projects they lead themselves. Admins can create one for anyone.
     #[graphql(name = "setProject")]
     async fn set_project(&self, ctx: &Context<'_>, input: SetProjectInput) -> Result<Project> {
+        require_member_or_admin(ctx, input.member_id)?;
         let pool = ctx
             .data::<Arc<PgPool>>()
             .expect("Pool must be found in context");
 
+        let mut tx = pool.begin().await?;
+
         let project = sqlx::query_as::<_, Project>(
-            "INSERT INTO Project (member_id, project_title) VALUES ($1, $2) RETURNING * ",
+            "INSERT INTO Project (member_id, title, description, repository_url, status, start_date, end_date, tags)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *",
         )
         .bind(input.member_id)
         .bind(input.title)
-        .fetch_one(pool.as_ref())
+        .bind(input.description)
+        .bind(input.repository_url)
+        .bind(input.status.unwrap_or(ProjectStatus::Idea))
+        .bind(input.start_date)
+        .bind(input.end_date)
+        .bind(input.tags.unwrap_or_default())
+        .fetch_one(&mut *tx)
         .await?;
+
+        sqlx::query("INSERT INTO ProjectMember (project_id, member_id, role) VALUES ($1, $2, $3)")
+            .bind(project.project_id)
+            .bind(project.member_id)
+            .bind(ProjectRole::Lead)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
         Ok(project)
     }
+
+    /// Leads and admins only.
+    #[graphql(name = "updateProject")]
+    async fn update_project(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateProjectInput,
+    ) -> Result<Project> {
+        let pool = ctx
+            .data::<Arc<PgPool>>()
+            .expect("Pool must be found in context");
+        require_lead_or_admin(ctx, input.project_id, pool).await?;
+
+        let mut tx = pool.begin().await?;
+        let mut query = QueryBuilder::new("UPDATE Project SET project_id = project_id");
+        set(&mut query, "title", input.title);
+        set_nullable(&mut query, "description", input.description);
+        set_nullable(&mut query, "repository_url", input.repository_url);
+        set(&mut query, "status", input.status);
+        set_nullable(&mut query, "start_date", input.start_date);
+        set_nullable(&mut query, "end_date", input.end_date);
+        set(&mut query, "tags", input.tags);
+        query.push(" WHERE project_id = ");
+        query.push_bind(input.project_id);
+        query.push(" RETURNING *");
+
+        let project = query
+            .build_query_as::<Project>()
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Project not found"))?;
+
+        emit(Event::ProjectUpdated(&project), &mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(project)
+    }
+
+    /// Returns the deleted project. Leads and admins only.
+    #[graphql(name = "deleteProject")]
+    async fn delete_project(&self, ctx: &Context<'_>, project_id: i32) -> Result<Project> {
+        let pool = ctx
+            .data::<Arc<PgPool>>()
+            .expect("Pool must be found in context");
+        require_lead_or_admin(ctx, project_id, pool).await?;
+
+        let mut tx = pool.begin().await?;
+        let project =
+            sqlx::query_as::<_, Project>("DELETE FROM Project WHERE project_id = $1 RETURNING *")
+                .bind(project_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or_else(|| async_graphql::Error::new("Project not found"))?;
+
+        emit(Event::ProjectDeleted(&project), &mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(project)
+    }
+
+    /// Leads and admins only.
+    #[graphql(name = "addProjectMember")]
+    async fn add_project_member(
+        &self,
+        ctx: &Context<'_>,
+        input: ProjectMemberInput,
+    ) -> Result<ProjectMember> {
+        let pool = ctx
+            .data::<Arc<PgPool>>()
+            .expect("Pool must be found in context");
+        require_lead_or_admin(ctx, input.project_id, pool).await?;
+
+        let mut tx = pool.begin().await?;
+        let project_member = sqlx::query_as::<_, ProjectMember>(
+            "INSERT INTO ProjectMember (project_id, member_id, role) VALUES ($1, $2, $3)
+            ON CONFLICT (project_id, member_id) DO UPDATE SET role = EXCLUDED.role
+            RETURNING *",
+        )
+        .bind(input.project_id)
+        .bind(input.member_id)
+        .bind(input.role.unwrap_or(ProjectRole::Contributor))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        require_lead_left(input.project_id, &mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(project_member)
+    }
+
+    /// Returns the removed membership. Leads and admins only.
+    #[graphql(name = "removeProjectMember")]
+    async fn remove_project_member(
+        &self,
+        ctx: &Context<'_>,
+        project_id: i32,
+        member_id: i32,
+    ) -> Result<ProjectMember> {
+        let pool = ctx
+            .data::<Arc<PgPool>>()
+            .expect("Pool must be found in context");
+        require_lead_or_admin(ctx, project_id, pool).await?;
+
+        let mut tx = pool.begin().await?;
+        let project_member = sqlx::query_as::<_, ProjectMember>(
+            "DELETE FROM ProjectMember WHERE project_id = $1 AND member_id = $2 RETURNING *",
+        )
+        .bind(project_id)
+        .bind(member_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Member is not part of this project"))?;
+
+        require_lead_left(project_id, &mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(project_member)
+    }
+
+    /// Imports a project's repository activity for a day, e.g. to backfill days before it was tracked.
+    /// Admins only, since imports spend the forge API quota the daily task needs.
+    #[graphql(name = "importProjectActivity")]
+    async fn import_project_activity(
+        &self,
+        ctx: &Context<'_>,
+        project_id: i32,
+        date: NaiveDate,
+    ) -> Result<ProjectActivity> {
+        require_admin(ctx)?;
+        let pool = ctx
+            .data::<Arc<PgPool>>()
+            .expect("Pool must be found in context");
+        let clients = ctx
+            .data::<ForgeClients>()
+            .expect("ForgeClients must be found in context");
+
+        let repository_url = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT repository_url FROM Project WHERE project_id = $1",
+        )
+        .bind(project_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Project not found"))?
+        .ok_or_else(|| async_graphql::Error::new("Project has no repository URL"))?;
+
+        import_project_activity(project_id, &repository_url, date, clients, pool.as_ref())
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
 }
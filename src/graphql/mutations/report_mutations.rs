@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use chrono::{Local, NaiveDate};
+use sqlx::PgPool;
+
+use crate::{
+    auth::require_admin,
+    config::timezone,
+    models::monthly_report::MonthlyReport,
+    reports::{generate_monthly_report, ReportRules},
+    streaks::StreakRules,
+};
+
+#[derive(Default)]
+pub struct ReportMutations;
+
+#[Object]
+impl ReportMutations {
+    /// Generates a month's report again, e.g. after correcting attendance. The daily task
+    /// generates the previous month's report on the 1st. Admins only.
+    #[graphql(name = "generateMonthlyReport")]
+    async fn generate_monthly_report(
+        &self,
+        ctx: &Context<'_>,
+        year: i32,
+        month: i32,
+    ) -> Result<MonthlyReport> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let rules = ctx
+            .data::<ReportRules>()
+            .expect("ReportRules must be in context.");
+        let streak_rules = ctx
+            .data::<StreakRules>()
+            .expect("StreakRules must be in context.");
+
+        let first = u32::try_from(month)
+            .ok()
+            .and_then(|month| NaiveDate::from_ymd_opt(year, month, 1))
+            .ok_or_else(|| async_graphql::Error::new("Invalid month"))?;
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        Ok(generate_monthly_report(first, now, rules, streak_rules, pool.as_ref()).await?)
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use chrono::{Datelike, Local};
+use sqlx::PgPool;
+
+use crate::{
+    auth::require_admin,
+    config::timezone,
+    lifecycle::rollover::{roll_over, undo_rollover, RolloverRules},
+    models::rollover::{RolloverOutcome, YearRollover},
+};
+
+#[derive(Default)]
+pub struct RolloverMutations;
+
+#[Object]
+impl RolloverMutations {
+    /// Moves active members up a year and final years to alumni. `academicYear` defaults
+    /// to the current year. Use `dryRun` to preview the changes without applying them.
+    #[graphql(name = "rolloverAcademicYear")]
+    async fn rollover_academic_year(
+        &self,
+        ctx: &Context<'_>,
+        academic_year: Option<i32>,
+        #[graphql(default = false)] dry_run: bool,
+    ) -> Result<RolloverOutcome> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        let academic_year = academic_year.unwrap_or(now.year());
+
+        Ok(roll_over(academic_year, now, false, dry_run, pool.as_ref()).await?)
+    }
+
+    /// Reverts a rollover, as long as its undo window hasn't closed.
+    #[graphql(name = "undoYearRollover")]
+    async fn undo_year_rollover(
+        &self,
+        ctx: &Context<'_>,
+        rollover_id: i32,
+    ) -> Result<YearRollover> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let rules = ctx
+            .data::<RolloverRules>()
+            .expect("RolloverRules must be in context.");
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        Ok(undo_rollover(rollover_id, now, rules, pool.as_ref()).await?)
+    }
+}
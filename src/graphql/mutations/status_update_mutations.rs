@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use chrono::Local;
+use sqlx::PgPool;
+
+use crate::{
+    auth::{require_admin, require_member_or_admin},
+    config::timezone,
+    models::status_update::{RecordStatusUpdateInput, StatusUpdate},
+    streaks::{record_status_update, refresh_streak, StreakRules},
+};
+
+#[derive(Default)]
+pub struct StatusUpdateMutations;
+
+#[Object]
+impl StatusUpdateMutations {
+    /// Records a member's status update for a day and recalculates their streak.
+    /// Only the first update for a member and day is stored, later calls return it unchanged.
+    /// Members record their own updates, admin tools anyone's.
+    #[graphql(name = "recordStatusUpdate")]
+    async fn record_status_update(
+        &self,
+        ctx: &Context<'_>,
+        input: RecordStatusUpdateInput,
+    ) -> Result<StatusUpdate> {
+        require_member_or_admin(ctx, input.member_id)?;
+        if input.posted_at.is_some() {
+            require_admin(ctx)?;
+        }
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let rules = ctx
+            .data::<StreakRules>()
+            .expect("StreakRules must be in context.");
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        let posted_at = input.posted_at.unwrap_or(now);
+        let status_update = record_status_update(&input, posted_at, pool.as_ref()).await?;
+
+        refresh_streak(input.member_id, now, rules, pool.as_ref()).await?;
+
+        Ok(status_update)
+    }
+}
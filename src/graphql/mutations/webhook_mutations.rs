@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use chrono::Local;
+use sqlx::PgPool;
+
+use crate::{
+    auth::require_admin,
+    config::timezone,
+    events,
+    models::webhook::{CreateWebhookSubscriptionInput, WebhookDelivery, WebhookSubscription},
+};
+
+#[derive(Default)]
+pub struct WebhookMutations;
+
+#[Object]
+impl WebhookMutations {
+    /// Starts sending events to `url`, signed with `secret`. Admins only.
+    #[graphql(name = "createWebhookSubscription")]
+    async fn create_webhook_subscription(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateWebhookSubscriptionInput,
+    ) -> Result<WebhookSubscription> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        let subscription = sqlx::query_as::<_, WebhookSubscription>(
+            "INSERT INTO WebhookSubscription (url, secret, events, created_at)
+            VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(&input.url)
+        .bind(&input.secret)
+        .bind(&input.events)
+        .bind(now)
+        .fetch_one(pool.as_ref())
+        .await?;
+
+        Ok(subscription)
+    }
+
+    /// Paused subscriptions don't get new events, and their pending deliveries wait
+    /// until they are active again. Admins only.
+    #[graphql(name = "setWebhookSubscriptionActive")]
+    async fn set_webhook_subscription_active(
+        &self,
+        ctx: &Context<'_>,
+        subscription_id: i32,
+        active: bool,
+    ) -> Result<WebhookSubscription> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let subscription = sqlx::query_as::<_, WebhookSubscription>(
+            "UPDATE WebhookSubscription SET active = $2 WHERE subscription_id = $1 RETURNING *",
+        )
+        .bind(subscription_id)
+        .bind(active)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Subscription not found"))?;
+
+        Ok(subscription)
+    }
+
+    /// Deletes a subscription along with its deliveries. Admins only.
+    #[graphql(name = "deleteWebhookSubscription")]
+    async fn delete_webhook_subscription(
+        &self,
+        ctx: &Context<'_>,
+        subscription_id: i32,
+    ) -> Result<WebhookSubscription> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let subscription = sqlx::query_as::<_, WebhookSubscription>(
+            "DELETE FROM WebhookSubscription WHERE subscription_id = $1 RETURNING *",
+        )
+        .bind(subscription_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Subscription not found"))?;
+
+        Ok(subscription)
+    }
+
+    /// Sends a `ping` event to the subscription, to check its receiver. Admins only.
+    #[graphql(name = "pingWebhookSubscription")]
+    async fn ping_webhook_subscription(
+        &self,
+        ctx: &Context<'_>,
+        subscription_id: i32,
+    ) -> Result<WebhookDelivery> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        events::ping(subscription_id, pool.as_ref())
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Subscription not found"))
+    }
+
+    /// Sends a delivery again as soon as possible, with a fresh set of attempts. Admins only.
+    #[graphql(name = "redeliverWebhook")]
+    async fn redeliver_webhook(
+        &self,
+        ctx: &Context<'_>,
+        delivery_id: i32,
+    ) -> Result<WebhookDelivery> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(
+            "UPDATE WebhookDelivery SET status = 'pending', attempts = 0, next_attempt_at = $2,
+                delivered_at = NULL
+            WHERE delivery_id = $1 RETURNING *",
+        )
+        .bind(delivery_id)
+        .bind(now)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Delivery not found"))?;
+
+        Ok(delivery)
+    }
+}
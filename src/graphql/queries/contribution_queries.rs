@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::models::contribution::{Contribution, ForgeAccount};
+use async_graphql::{Context, Object, Result};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+#[derive(Default)]
+pub struct ContributionQueries;
+
+#[Object]
+impl ContributionQueries {
+    /// Contributions credited through forge webhooks, newest first. All filters are optional.
+    async fn contributions(
+        &self,
+        ctx: &Context<'_>,
+        member_id: Option<i32>,
+        project_id: Option<i32>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<Vec<Contribution>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let mut query = sqlx::QueryBuilder::new("SELECT * FROM Contribution WHERE 1=1");
+
+        if let Some(m) = member_id {
+            query.push(" AND member_id = ");
+            query.push_bind(m);
+        }
+
+        if let Some(p) = project_id {
+            query.push(" AND project_id = ");
+            query.push_bind(p);
+        }
+
+        if let Some(f) = from {
+            query.push(" AND contributed_at::date >= ");
+            query.push_bind(f);
+        }
+
+        if let Some(t) = to {
+            query.push(" AND contributed_at::date <= ");
+            query.push_bind(t);
+        }
+
+        query.push(" ORDER BY contributed_at DESC");
+
+        let contributions = query
+            .build_query_as::<Contribution>()
+            .fetch_all(pool.as_ref())
+            .await?;
+
+        Ok(contributions)
+    }
+
+    /// Linked forge accounts, e.g. `approved: false` for the ones waiting for an admin.
+    /// Both filters are optional.
+    #[graphql(name = "forgeAccounts")]
+    async fn forge_accounts(
+        &self,
+        ctx: &Context<'_>,
+        member_id: Option<i32>,
+        approved: Option<bool>,
+    ) -> Result<Vec<ForgeAccount>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, ForgeAccount>(
+            "SELECT * FROM ForgeAccount
+            WHERE ($1::int IS NULL OR member_id = $1) AND ($2::bool IS NULL OR approved = $2)
+            ORDER BY member_id, host",
+        )
+        .bind(member_id)
+        .bind(approved)
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+}
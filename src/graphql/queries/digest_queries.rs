@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use chrono::{Duration, Local, NaiveDate};
+use sqlx::PgPool;
+
+use crate::{
+    auth::require_admin, config::timezone, email::digest::Digests, models::digest::GroupDigest,
+    streaks::StreakRules,
+};
+
+#[derive(Default)]
+pub struct DigestQueries;
+
+#[Object]
+impl DigestQueries {
+    /// Renders the digest of a group's week ending on `to`, yesterday by default, without
+    /// sending it. Admins only.
+    #[graphql(name = "previewGroupDigest")]
+    async fn preview_group_digest(
+        &self,
+        ctx: &Context<'_>,
+        group_id: i32,
+        to: Option<NaiveDate>,
+    ) -> Result<GroupDigest> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let digests = ctx.data::<Digests>().expect("Digests must be in context.");
+        let streak_rules = ctx
+            .data::<StreakRules>()
+            .expect("StreakRules must be in context.");
+
+        let to = to.unwrap_or_else(|| {
+            Local::now().with_timezone(&timezone()).date_naive() - Duration::days(1)
+        });
+        digests
+            .render(group_id, to, streak_rules, pool.as_ref())
+            .await?
+            .ok_or_else(|| "Group not found".into())
+    }
+}
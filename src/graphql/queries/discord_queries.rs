@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use crate::{
+    auth::require_admin,
+    models::discord::{DiscordChannel, DiscordRoute, NotificationKind},
+    notifier::Notifier,
+};
+use async_graphql::{ComplexObject, Context, Object, Result};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+#[derive(Default)]
+pub struct DiscordQueries;
+
+#[Object]
+impl DiscordQueries {
+    /// Admins only.
+    #[graphql(name = "discordChannels")]
+    async fn discord_channels(&self, ctx: &Context<'_>) -> Result<Vec<DiscordChannel>> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, DiscordChannel>("SELECT * FROM DiscordChannel ORDER BY name")
+                .fetch_all(pool.as_ref())
+                .await?,
+        )
+    }
+
+    /// Renders a notification about `date` without posting it, e.g. to try out a template.
+    /// Null if there's nothing to notify about. Admins only.
+    #[graphql(name = "previewDiscordNotification")]
+    async fn preview_discord_notification(
+        &self,
+        ctx: &Context<'_>,
+        kind: NotificationKind,
+        date: NaiveDate,
+        template: Option<String>,
+    ) -> Result<Option<String>> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let notifier = ctx
+            .data::<Notifier>()
+            .expect("Notifier must be in context.");
+
+        Ok(notifier
+            .render(kind, date, template.as_deref(), pool.as_ref())
+            .await?)
+    }
+}
+
+#[ComplexObject]
+impl DiscordChannel {
+    #[graphql(complexity = 2)]
+    async fn routes(&self, ctx: &Context<'_>) -> Vec<DiscordRoute> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, DiscordRoute>(
+            "SELECT * FROM DiscordRoute WHERE channel_id = $1 ORDER BY kind",
+        )
+        .bind(self.channel_id)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use crate::models::{
+    group::{Group, GroupAttendance, GroupStreaks},
+    member::{Member, MemberStatus},
+};
+use async_graphql::{ComplexObject, Context, Object, Result};
+use sqlx::PgPool;
+
+#[derive(Default)]
+pub struct GroupQueries;
+
+#[Object]
+impl GroupQueries {
+    async fn groups(&self, ctx: &Context<'_>) -> Result<Vec<Group>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Group>("SELECT * FROM MemberGroup ORDER BY group_id")
+                .fetch_all(pool.as_ref())
+                .await?,
+        )
+    }
+
+    async fn group(&self, ctx: &Context<'_>, group_id: i32) -> Result<Group> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, Group>("SELECT * FROM MemberGroup WHERE group_id = $1")
+            .bind(group_id)
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Group not found"))
+    }
+}
+
+#[ComplexObject]
+impl Group {
+    /// Only active members are returned unless another `status` is given. Pass `null` for everyone.
+    #[graphql(complexity = 5)]
+    async fn members(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default_with = "Some(MemberStatus::Active)")] status: Option<MemberStatus>,
+    ) -> Vec<Member> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, Member>(
+            "SELECT * FROM Member WHERE group_id = $1 AND ($2::member_status IS NULL OR status = $2)",
+        )
+        .bind(self.group_id)
+        .bind(status)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+
+    #[graphql(complexity = 2)]
+    async fn mentor(&self, ctx: &Context<'_>) -> Result<Option<Member>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let Some(mentor_id) = self.mentor_id else {
+            return Ok(None);
+        };
+
+        Ok(
+            sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE member_id = $1")
+                .bind(mentor_id)
+                .fetch_optional(pool.as_ref())
+                .await?,
+        )
+    }
+
+    /// Attendance of the group's members for one month, from `AttendanceSummary`.
+    #[graphql(complexity = 5)]
+    async fn attendance(
+        &self,
+        ctx: &Context<'_>,
+        year: i32,
+        month: i32,
+    ) -> Result<GroupAttendance> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, GroupAttendance>(
+            "SELECT $2 AS year, $3 AS month,
+                COUNT(m.member_id) AS member_count,
+                COALESCE(SUM(s.days_attended), 0)::int8 AS total_days_attended,
+                COALESCE(AVG(COALESCE(s.days_attended, 0)), 0)::float8 AS average_days_attended
+            FROM Member m
+            LEFT JOIN AttendanceSummary s
+                ON s.member_id = m.member_id AND s.year = $2 AND s.month = $3
+            WHERE m.group_id = $1 AND m.status = 'active'",
+        )
+        .bind(self.group_id)
+        .bind(year)
+        .bind(month)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+
+    #[graphql(complexity = 5)]
+    async fn streaks(&self, ctx: &Context<'_>) -> Result<GroupStreaks> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, GroupStreaks>(
+            "SELECT COUNT(m.member_id) AS member_count,
+                COUNT(*) FILTER (WHERE s.current_streak > 0) AS active_streaks,
+                COALESCE(AVG(COALESCE(s.current_streak, 0)), 0)::float8 AS average_current_streak,
+                COALESCE(MAX(s.current_streak), 0) AS longest_current_streak,
+                COALESCE(MAX(s.max_streak), 0) AS longest_max_streak
+            FROM Member m
+            LEFT JOIN StatusUpdateStreak s ON s.member_id = m.member_id
+            WHERE m.group_id = $1 AND m.status = 'active'",
+        )
+        .bind(self.group_id)
+        .fetch_one(pool.as_ref())
+        .await?)
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use crate::config::timezone;
+use crate::models::leaderboard::{LeaderboardEntry, LeaderboardKind, LeaderboardPeriod};
+use async_graphql::{Context, Object, Result};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Default)]
+pub struct LeaderboardQueries;
+
+#[Object]
+impl LeaderboardQueries {
+    /// Active members ranked by `kind`, highest first. Ties share a rank and are ordered by name.
+    async fn leaderboard(
+        &self,
+        ctx: &Context<'_>,
+        kind: LeaderboardKind,
+        #[graphql(default_with = "LeaderboardPeriod::Month")] period: LeaderboardPeriod,
+        year: Option<i32>,
+        group_id: Option<i32>,
+        #[graphql(default = 10)] limit: i64,
+    ) -> Result<Vec<LeaderboardEntry>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let today = Local::now().with_timezone(&timezone()).date_naive();
+        let since = period_start(period, today);
+
+        let mut query =
+            QueryBuilder::new("WITH scores AS (SELECT m.member_id, m.name, m.year, m.group_id, (");
+        push_score(&mut query, kind, since);
+        query.push(")::float8 AS score FROM Member m WHERE m.status = 'active'");
+
+        if let Some(y) = year {
+            query.push(" AND m.year = ");
+            query.push_bind(y);
+        }
+
+        if let Some(g) = group_id {
+            query.push(" AND m.group_id = ");
+            query.push_bind(g);
+        }
+
+        query.push(
+            ") SELECT RANK() OVER (ORDER BY score DESC) AS rank, member_id, name, year, group_id, score
+            FROM scores ORDER BY rank, name LIMIT ",
+        );
+        query.push_bind(limit.clamp(1, MAX_LIMIT));
+
+        let entries = query
+            .build_query_as::<LeaderboardEntry>()
+            .fetch_all(pool.as_ref())
+            .await?;
+
+        Ok(entries)
+    }
+}
+
+fn period_start(period: LeaderboardPeriod, today: NaiveDate) -> Option<NaiveDate> {
+    match period {
+        LeaderboardPeriod::Week => Some(today - Duration::days(6)),
+        LeaderboardPeriod::Month => today.with_day(1),
+        LeaderboardPeriod::Year => today.with_ordinal(1),
+        LeaderboardPeriod::AllTime => None,
+    }
+}
+
+/// Pushes the expression computing a member's score, relative to the `Member m` being ranked.
+fn push_score(
+    query: &mut QueryBuilder<'_, Postgres>,
+    kind: LeaderboardKind,
+    since: Option<NaiveDate>,
+) {
+    match kind {
+        LeaderboardKind::CurrentStreak => {
+            query.push("SELECT COALESCE(MAX(current_streak), 0) FROM StatusUpdateStreak s WHERE s.member_id = m.member_id");
+        }
+        LeaderboardKind::MaxStreak => {
+            query.push("SELECT COALESCE(MAX(max_streak), 0) FROM StatusUpdateStreak s WHERE s.member_id = m.member_id");
+        }
+        LeaderboardKind::DaysAttended => push_days_attended(query, since),
+        LeaderboardKind::HoursInLab => push_hours_in_lab(query, since),
+        LeaderboardKind::ActivityScore => {
+            query.push("(");
+            push_days_attended(query, since);
+            query.push(") + (SELECT COUNT(*) FROM StatusUpdate u WHERE u.member_id = m.member_id");
+            push_since(query, "u.date", since);
+            query.push(") + (");
+            push_hours_in_lab(query, since);
+            query.push(") / 4");
+        }
+    }
+}
+
+fn push_days_attended(query: &mut QueryBuilder<'_, Postgres>, since: Option<NaiveDate>) {
+    query
+        .push("SELECT COUNT(*) FROM Attendance a WHERE a.member_id = m.member_id AND a.is_present");
+    push_since(query, "a.date", since);
+}
+
+fn push_hours_in_lab(query: &mut QueryBuilder<'_, Postgres>, since: Option<NaiveDate>) {
+    query.push(
+        "SELECT COALESCE(SUM(EXTRACT(EPOCH FROM (a.time_out - a.time_in))), 0) / 3600
+        FROM Attendance a WHERE a.member_id = m.member_id AND a.is_present",
+    );
+    push_since(query, "a.date", since);
+}
+
+fn push_since(query: &mut QueryBuilder<'_, Postgres>, column: &str, since: Option<NaiveDate>) {
+    if let Some(since) = since {
+        query.push(format!(" AND {} >= ", column));
+        query.push_bind(since);
+    }
+}
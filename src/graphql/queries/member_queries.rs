@@ -2,11 +2,16 @@ use async_graphql::{ComplexObject, Context, Object, Result};
 use sqlx::PgPool;
 use std::sync::Arc;
 
-use crate::models::{
-    attendance::{AttendanceInfo, AttendanceSummaryInfo},
-    member::Member,
-    project::Project,
-    status_update_streak::StatusUpdateStreakInfo,
+use crate::{
+    auth::Visibility,
+    models::{
+        attendance::{AttendanceInfo, AttendanceSummaryInfo},
+        group::Group,
+        member::{Member, MemberStatus, MemberStatusChange, Sex},
+        profile::MemberProfile,
+        project::Project,
+        status_update_streak::StatusUpdateStreakInfo,
+    },
 };
 
 #[derive(Default)]
@@ -14,11 +19,13 @@ pub struct MemberQueries;
 
 #[Object]
 impl MemberQueries {
+    /// Only active members are returned unless another `status` is given. Pass `null` for everyone.
     pub async fn members(
         &self,
         ctx: &Context<'_>,
         year: Option<i32>,
         group_id: Option<i32>,
+        #[graphql(default_with = "Some(MemberStatus::Active)")] status: Option<MemberStatus>,
     ) -> Result<Vec<Member>> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
@@ -34,6 +41,11 @@ impl MemberQueries {
             query.push_bind(g);
         }
 
+        if let Some(s) = status {
+            query.push(" AND status = ");
+            query.push_bind(s);
+        }
+
         let members = query
             .build_query_as::<Member>()
             .fetch_all(pool.as_ref())
@@ -45,6 +57,72 @@ impl MemberQueries {
 
 #[ComplexObject]
 impl Member {
+    async fn email(&self, ctx: &Context<'_>) -> Option<String> {
+        Visibility::MemberOnly.reveal(ctx, self.member_id, self.email.clone())
+    }
+
+    async fn sex(&self, ctx: &Context<'_>) -> Option<Sex> {
+        Visibility::AdminOnly.reveal(ctx, self.member_id, self.sex)
+    }
+
+    async fn hostel(&self, ctx: &Context<'_>) -> Option<String> {
+        Visibility::AdminOnly.reveal(ctx, self.member_id, self.hostel.clone())
+    }
+
+    async fn mac_address(&self, ctx: &Context<'_>) -> Option<String> {
+        Visibility::AdminOnly.reveal(ctx, self.member_id, self.mac_address.clone())
+    }
+
+    async fn discord_id(&self, ctx: &Context<'_>) -> Option<String> {
+        Visibility::MemberOnly.reveal(ctx, self.member_id, self.discord_id.clone())
+    }
+
+    #[graphql(complexity = 2)]
+    async fn group(&self, ctx: &Context<'_>) -> Result<Group> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Group>("SELECT * FROM MemberGroup WHERE group_id = $1")
+                .bind(self.group_id)
+                .fetch_one(pool.as_ref())
+                .await?,
+        )
+    }
+
+    /// Everything the member filled in on their profile, regardless of visibility.
+    #[graphql(complexity = 2)]
+    async fn profile(&self, ctx: &Context<'_>) -> Result<Option<MemberProfile>> {
+        if Visibility::AdminOnly
+            .reveal(ctx, self.member_id, ())
+            .is_none()
+        {
+            return Ok(None);
+        }
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, MemberProfile>("SELECT * FROM MemberProfile WHERE member_id = $1")
+                .bind(self.member_id)
+                .fetch_optional(pool.as_ref())
+                .await?,
+        )
+    }
+
+    /// Every status the member has had or is scheduled to have, oldest first.
+    #[graphql(name = "statusHistory", complexity = 5)]
+    async fn status_history(&self, ctx: &Context<'_>) -> Vec<MemberStatusChange> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, MemberStatusChange>(
+            "SELECT * FROM MemberStatusChange WHERE member_id = $1 ORDER BY effective_from, change_id",
+        )
+        .bind(self.member_id)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+
+    #[graphql(complexity = 10)]
     async fn attendance(&self, ctx: &Context<'_>) -> Vec<AttendanceInfo> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
@@ -57,7 +135,7 @@ impl Member {
         .unwrap_or_default()
     }
 
-    #[graphql(name = "attendanceSummary")]
+    #[graphql(name = "attendanceSummary", complexity = 5)]
     async fn attendance_summary(&self, ctx: &Context<'_>) -> Vec<AttendanceSummaryInfo> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
@@ -70,11 +148,12 @@ impl Member {
         .unwrap_or_default()
     }
 
+    #[graphql(complexity = 2)]
     async fn streak(&self, ctx: &Context<'_>) -> Vec<StatusUpdateStreakInfo> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
         sqlx::query_as::<_, StatusUpdateStreakInfo>(
-            "SELECT current_streak, max_streak FROM StatusUpdateStreak WHERE member_id = $1",
+            "SELECT current_streak, max_streak, missed_streak FROM StatusUpdateStreak WHERE member_id = $1",
         )
         .bind(self.member_id)
         .fetch_all(pool.as_ref())
@@ -82,13 +161,18 @@ impl Member {
         .unwrap_or_default()
     }
 
+    #[graphql(complexity = 5)]
     async fn projects(&self, ctx: &Context<'_>) -> Vec<Project> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        sqlx::query_as::<_, Project>("SELECT project_id, title FROM Project WHERE member_id = $1")
-            .bind(self.member_id)
-            .fetch_all(pool.as_ref())
-            .await
-            .unwrap_or_default()
+        sqlx::query_as::<_, Project>(
+            "SELECT p.* FROM Project p
+            JOIN ProjectMember pm ON p.project_id = pm.project_id
+            WHERE pm.member_id = $1",
+        )
+        .bind(self.member_id)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
     }
 }
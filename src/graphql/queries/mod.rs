@@ -1,9 +1,27 @@
 pub mod attendance_queries;
+pub mod contribution_queries;
+pub mod digest_queries;
+pub mod discord_queries;
+pub mod group_queries;
+pub mod leaderboard_queries;
 pub mod member_queries;
+pub mod profile_queries;
 pub mod project_queries;
+pub mod report_queries;
+pub mod rollover_queries;
 pub mod streak_queries;
+pub mod webhook_queries;
 
 pub use attendance_queries::AttendanceQueries;
+pub use contribution_queries::ContributionQueries;
+pub use digest_queries::DigestQueries;
+pub use discord_queries::DiscordQueries;
+pub use group_queries::GroupQueries;
+pub use leaderboard_queries::LeaderboardQueries;
 pub use member_queries::MemberQueries;
+pub use profile_queries::ProfileQueries;
 pub use project_queries::ProjectQueries;
+pub use report_queries::ReportQueries;
+pub use rollover_queries::RolloverQueries;
 pub use streak_queries::StreakQueries;
+pub use webhook_queries::WebhookQueries;
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use sqlx::PgPool;
+
+use crate::models::{
+    member::Member,
+    profile::{MemberProfile, PublicProfile},
+};
+
+#[derive(Default)]
+pub struct ProfileQueries;
+
+#[Object]
+impl ProfileQueries {
+    /// A member's public profile, as shown on Home. Members who haven't filled in their
+    /// profile only show their name and year. Hidden profiles are reported as not found.
+    async fn profile(&self, ctx: &Context<'_>, roll_no: String) -> Result<PublicProfile> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let member = sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE roll_no = $1")
+            .bind(roll_no.trim().to_ascii_uppercase())
+            .fetch_optional(pool.as_ref())
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("Profile not found"))?;
+
+        let profile =
+            sqlx::query_as::<_, MemberProfile>("SELECT * FROM MemberProfile WHERE member_id = $1")
+                .bind(member.member_id)
+                .fetch_optional(pool.as_ref())
+                .await?;
+
+        let public = PublicProfile {
+            roll_no: member.roll_no,
+            name: member.name,
+            year: member.year,
+            ..Default::default()
+        };
+
+        let Some(profile) = profile else {
+            return Ok(public);
+        };
+        if !profile.is_public {
+            return Err(async_graphql::Error::new("Profile not found"));
+        }
+
+        let (website_url, github_handle, gitlab_handle, linkedin_handle) = if profile.show_socials {
+            (
+                profile.website_url,
+                profile.github_handle,
+                profile.gitlab_handle,
+                profile.linkedin_handle,
+            )
+        } else {
+            Default::default()
+        };
+
+        Ok(PublicProfile {
+            bio: profile.bio,
+            pronouns: profile.pronouns,
+            avatar_url: profile.avatar_url,
+            website_url,
+            github_handle,
+            gitlab_handle,
+            linkedin_handle,
+            skills: profile.skills,
+            interests: profile.interests,
+            email: profile.show_email.then_some(member.email),
+            ..public
+        })
+    }
+}
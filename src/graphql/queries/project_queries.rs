@@ -1,7 +1,12 @@
 use std::sync::Arc;
 
-use crate::models::project::Project;
-use async_graphql::{Context, Object, Result};
+use crate::models::{
+    member::Member,
+    project::{Project, ProjectMember, ProjectStatus},
+    project_activity::ProjectActivity,
+};
+use async_graphql::{ComplexObject, Context, Object, Result};
+use chrono::NaiveDate;
 use sqlx::PgPool;
 
 #[derive(Default)]
@@ -9,13 +14,96 @@ pub struct ProjectQueries;
 
 #[Object]
 impl ProjectQueries {
-    pub async fn projects(&self, ctx: &Context<'_>) -> Result<Vec<Project>> {
+    pub async fn projects(
+        &self,
+        ctx: &Context<'_>,
+        member_id: Option<i32>,
+        status: Option<ProjectStatus>,
+        tag: Option<String>,
+    ) -> Result<Vec<Project>> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        let projects = sqlx::query_as::<_, Project>("SELECT * FROM Project")
+        let mut query = sqlx::QueryBuilder::new("SELECT * FROM Project WHERE 1=1");
+
+        if let Some(m) = member_id {
+            query.push(
+                " AND project_id IN (SELECT project_id FROM ProjectMember WHERE member_id = ",
+            );
+            query.push_bind(m);
+            query.push(")");
+        }
+
+        if let Some(s) = status {
+            query.push(" AND status = ");
+            query.push_bind(s);
+        }
+
+        if let Some(t) = tag {
+            query.push(" AND ");
+            query.push_bind(t);
+            query.push(" = ANY(tags)");
+        }
+
+        let projects = query
+            .build_query_as::<Project>()
             .fetch_all(pool.as_ref())
             .await?;
 
         Ok(projects)
     }
 }
+
+#[ComplexObject]
+impl Project {
+    /// Everyone working on the project, leads first.
+    #[graphql(complexity = 5)]
+    async fn members(&self, ctx: &Context<'_>) -> Vec<ProjectMember> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, ProjectMember>(
+            "SELECT * FROM ProjectMember WHERE project_id = $1 ORDER BY role, joined_at",
+        )
+        .bind(self.project_id)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Daily repository activity from `from` to `to`, both inclusive.
+    #[graphql(complexity = 5)]
+    async fn activity(
+        &self,
+        ctx: &Context<'_>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Vec<ProjectActivity> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, ProjectActivity>(
+            "SELECT * FROM ProjectActivity
+            WHERE project_id = $1 AND date BETWEEN $2 AND $3
+            ORDER BY date",
+        )
+        .bind(self.project_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+}
+
+#[ComplexObject]
+impl ProjectMember {
+    #[graphql(complexity = 2)]
+    async fn member(&self, ctx: &Context<'_>) -> Result<Member> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE member_id = $1")
+                .bind(self.member_id)
+                .fetch_one(pool.as_ref())
+                .await?,
+        )
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use crate::{
+    auth::require_admin,
+    models::{
+        group::Group,
+        member::Member,
+        monthly_report::{GroupMonthlyReport, MemberMonthlyReport, MonthlyReport},
+    },
+};
+use async_graphql::{ComplexObject, Context, Object, Result};
+use sqlx::PgPool;
+
+#[derive(Default)]
+pub struct ReportQueries;
+
+#[Object]
+impl ReportQueries {
+    /// The report of a month. Admins only.
+    #[graphql(name = "monthlyReport")]
+    async fn monthly_report(
+        &self,
+        ctx: &Context<'_>,
+        year: i32,
+        month: i32,
+    ) -> Result<MonthlyReport> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, MonthlyReport>(
+            "SELECT * FROM MonthlyReport WHERE year = $1 AND month = $2",
+        )
+        .bind(year)
+        .bind(month)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| async_graphql::Error::new("Report not found"))
+    }
+
+    /// Every generated report, most recent month first. Admins only.
+    #[graphql(name = "monthlyReports")]
+    async fn monthly_reports(&self, ctx: &Context<'_>) -> Result<Vec<MonthlyReport>> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, MonthlyReport>(
+            "SELECT * FROM MonthlyReport ORDER BY year DESC, month DESC",
+        )
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+}
+
+#[ComplexObject]
+impl MonthlyReport {
+    /// One entry per member, optionally only those in `groupId`.
+    #[graphql(complexity = 5)]
+    async fn members(&self, ctx: &Context<'_>, group_id: Option<i32>) -> Vec<MemberMonthlyReport> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, MemberMonthlyReport>(
+            "SELECT * FROM MemberMonthlyReport
+            WHERE report_id = $1 AND ($2::int IS NULL OR group_id = $2)
+            ORDER BY attendance_percentage DESC, member_id",
+        )
+        .bind(self.report_id)
+        .bind(group_id)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+
+    #[graphql(complexity = 5)]
+    async fn groups(&self, ctx: &Context<'_>) -> Vec<GroupMonthlyReport> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, GroupMonthlyReport>(
+            "SELECT * FROM GroupMonthlyReport WHERE report_id = $1 ORDER BY group_id",
+        )
+        .bind(self.report_id)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+}
+
+#[ComplexObject]
+impl MemberMonthlyReport {
+    #[graphql(complexity = 2)]
+    async fn member(&self, ctx: &Context<'_>) -> Result<Member> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Member>("SELECT * FROM Member WHERE member_id = $1")
+                .bind(self.member_id)
+                .fetch_one(pool.as_ref())
+                .await?,
+        )
+    }
+}
+
+#[ComplexObject]
+impl GroupMonthlyReport {
+    #[graphql(complexity = 2)]
+    async fn group(&self, ctx: &Context<'_>) -> Result<Group> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, Group>("SELECT * FROM MemberGroup WHERE group_id = $1")
+                .bind(self.group_id)
+                .fetch_one(pool.as_ref())
+                .await?,
+        )
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::{
+    auth::require_admin,
+    lifecycle::rollover::RolloverRules,
+    models::rollover::{RolloverChange, YearRollover},
+};
+use async_graphql::{ComplexObject, Context, Object, Result};
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+
+#[derive(Default)]
+pub struct RolloverQueries;
+
+#[Object]
+impl RolloverQueries {
+    /// Past rollovers, most recent first.
+    #[graphql(name = "yearRollovers")]
+    async fn year_rollovers(&self, ctx: &Context<'_>) -> Result<Vec<YearRollover>> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, YearRollover>(
+            "SELECT * FROM YearRollover ORDER BY performed_at DESC",
+        )
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+}
+
+#[ComplexObject]
+impl YearRollover {
+    #[graphql(complexity = 5)]
+    async fn changes(&self, ctx: &Context<'_>) -> Vec<RolloverChange> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, RolloverChange>(
+            "SELECT e.member_id, m.roll_no, m.name, e.previous_year, e.new_year, e.graduated
+            FROM YearRolloverEntry e JOIN Member m ON m.member_id = e.member_id
+            WHERE e.rollover_id = $1 ORDER BY e.previous_year DESC, m.name",
+        )
+        .bind(self.rollover_id)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Last moment the rollover can be undone. Null once it has been undone.
+    #[graphql(name = "undoableUntil")]
+    async fn undoable_until(&self, ctx: &Context<'_>) -> Option<NaiveDateTime> {
+        let rules = ctx
+            .data::<RolloverRules>()
+            .expect("RolloverRules must be in context.");
+
+        match self.undone_at {
+            Some(_) => None,
+            None => Some(self.performed_at + rules.undo_window),
+        }
+    }
+}
@@ -1,9 +1,19 @@
 use std::sync::Arc;
 
-use crate::models::status_update_streak::StatusUpdateStreak as Streak;
+use crate::config::timezone;
+use crate::models::status_update_streak::{
+    StatusUpdateDay, StatusUpdateStreak as Streak, StreakRun,
+};
+use crate::streaks::{
+    calendar, fetch_updates, fetch_updates_between, joined_on, streak_runs, StreakRules,
+};
 use async_graphql::{Context, Object, Result};
+use chrono::{Local, NaiveDate};
 use sqlx::PgPool;
 
+/// Longest range `statusUpdateCalendar` will describe in one request.
+const MAX_CALENDAR_DAYS: i64 = 366;
+
 #[derive(Default)]
 pub struct StreakQueries;
 
@@ -12,12 +22,12 @@ impl StreakQueries {
     async fn streak(&self, ctx: &Context<'_>, member_id: i32) -> Result<Streak> {
         let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
 
-        Ok(sqlx::query_as::<_, Streak>(
-            "SELECT current_streak, max_streak FROM StatusUpdateStreak WHERE member_id = $1",
+        Ok(
+            sqlx::query_as::<_, Streak>("SELECT * FROM StatusUpdateStreak WHERE member_id = $1")
+                .bind(member_id)
+                .fetch_one(pool.as_ref())
+                .await?,
         )
-        .bind(member_id)
-        .fetch_one(pool.as_ref())
-        .await?)
     }
 
     async fn streaks(&self, ctx: &Context<'_>) -> Result<Vec<Streak>> {
@@ -29,4 +39,50 @@ impl StreakQueries {
                 .await?,
         )
     }
+
+    /// Every day from `from` to `to` with whether the member posted an update, missed it or wasn't expected to.
+    /// Starts on the day the member joined if that's later than `from`.
+    #[graphql(name = "statusUpdateCalendar")]
+    async fn status_update_calendar(
+        &self,
+        ctx: &Context<'_>,
+        member_id: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<StatusUpdateDay>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let rules = ctx
+            .data::<StreakRules>()
+            .expect("StreakRules must be in context.");
+
+        if from > to {
+            return Err(async_graphql::Error::new("`from` must not be after `to`"));
+        }
+        if (to - from).num_days() >= MAX_CALENDAR_DAYS {
+            return Err(async_graphql::Error::new(format!(
+                "Calendar range cannot exceed {} days",
+                MAX_CALENDAR_DAYS
+            )));
+        }
+
+        let now = Local::now().with_timezone(&timezone()).naive_local();
+        let joined_on = joined_on(member_id, pool.as_ref()).await?;
+        let updates = fetch_updates_between(member_id, from, to, pool.as_ref()).await?;
+
+        Ok(calendar(&updates, from, to, joined_on, now, rules))
+    }
+
+    /// Every streak a member has had, oldest first.
+    #[graphql(name = "streakHistory")]
+    async fn streak_history(&self, ctx: &Context<'_>, member_id: i32) -> Result<Vec<StreakRun>> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+        let rules = ctx
+            .data::<StreakRules>()
+            .expect("StreakRules must be in context.");
+
+        let today = Local::now().with_timezone(&timezone()).date_naive();
+        let updates = fetch_updates(member_id, today, pool.as_ref()).await?;
+
+        Ok(streak_runs(&updates, rules))
+    }
 }
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use crate::{
+    auth::require_admin,
+    models::webhook::{
+        DeliveryStatus, WebhookDelivery, WebhookDeliveryAttempt, WebhookEvent, WebhookSubscription,
+    },
+};
+use async_graphql::{ComplexObject, Context, Object, Result};
+use sqlx::PgPool;
+
+const MAX_LIMIT: i64 = 200;
+
+#[derive(Default)]
+pub struct WebhookQueries;
+
+#[Object]
+impl WebhookQueries {
+    /// Admins only.
+    #[graphql(name = "webhookSubscriptions")]
+    async fn webhook_subscriptions(&self, ctx: &Context<'_>) -> Result<Vec<WebhookSubscription>> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(sqlx::query_as::<_, WebhookSubscription>(
+            "SELECT * FROM WebhookSubscription ORDER BY subscription_id",
+        )
+        .fetch_all(pool.as_ref())
+        .await?)
+    }
+
+    /// Most recent deliveries first. Admins only.
+    #[graphql(name = "webhookDeliveries")]
+    async fn webhook_deliveries(
+        &self,
+        ctx: &Context<'_>,
+        subscription_id: Option<i32>,
+        status: Option<DeliveryStatus>,
+        #[graphql(default = 50)] limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        require_admin(ctx)?;
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        let mut query = sqlx::QueryBuilder::new("SELECT * FROM WebhookDelivery WHERE 1=1");
+
+        if let Some(s) = subscription_id {
+            query.push(" AND subscription_id = ");
+            query.push_bind(s);
+        }
+
+        if let Some(s) = status {
+            query.push(" AND status = ");
+            query.push_bind(s);
+        }
+
+        query.push(" ORDER BY delivery_id DESC LIMIT ");
+        query.push_bind(limit.clamp(1, MAX_LIMIT));
+
+        Ok(query
+            .build_query_as::<WebhookDelivery>()
+            .fetch_all(pool.as_ref())
+            .await?)
+    }
+}
+
+#[ComplexObject]
+impl WebhookDelivery {
+    #[graphql(complexity = 2)]
+    async fn event(&self, ctx: &Context<'_>) -> Result<WebhookEvent> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        Ok(
+            sqlx::query_as::<_, WebhookEvent>("SELECT * FROM WebhookEvent WHERE event_id = $1")
+                .bind(self.event_id)
+                .fetch_one(pool.as_ref())
+                .await?,
+        )
+    }
+
+    /// Every request sent for this delivery, oldest first.
+    #[graphql(complexity = 5)]
+    async fn log(&self, ctx: &Context<'_>) -> Vec<WebhookDeliveryAttempt> {
+        let pool = ctx.data::<Arc<PgPool>>().expect("Pool must be in context.");
+
+        sqlx::query_as::<_, WebhookDeliveryAttempt>(
+            "SELECT * FROM WebhookDeliveryAttempt WHERE delivery_id = $1 ORDER BY attempt_id",
+        )
+        .bind(self.delivery_id)
+        .fetch_all(pool.as_ref())
+        .await
+        .unwrap_or_default()
+    }
+}
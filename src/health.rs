@@ -0,0 +1,221 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use chrono::{Local, NaiveDateTime};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::config::timezone;
+use crate::{supervisor::TaskStatuses, MIGRATOR};
+
+/// Database checks taking longer than this count as failed, so probes get an answer in time.
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `/ready` needs no token, so it checks the database at most this often.
+const DATABASE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The daily task runs once a day, so a successful run is overdue after this long.
+const DAILY_RUN_GRACE: chrono::Duration = chrono::Duration::hours(26);
+
+/// What the daily task has been doing, shared with `/ready`.
+#[derive(Clone, Default)]
+pub struct SchedulerStatus(Arc<Mutex<SchedulerState>>);
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SchedulerState {
+    running: bool,
+    started_at: Option<NaiveDateTime>,
+    last_run_at: Option<NaiveDateTime>,
+    last_successful_run_at: Option<NaiveDateTime>,
+}
+
+impl SchedulerStatus {
+    /// Marks the scheduler as running until the returned guard is dropped, which also
+    /// happens if the scheduler panics.
+    pub fn start(&self, now: NaiveDateTime) -> RunningGuard {
+        let mut state = self.lock();
+        state.running = true;
+        state.started_at = Some(now);
+        RunningGuard(self.clone())
+    }
+
+    /// Records a finished run of the daily task.
+    pub fn record_run(&self, finished_at: NaiveDateTime, succeeded: bool) {
+        let mut state = self.lock();
+        state.last_run_at = Some(finished_at);
+        if succeeded {
+            state.last_successful_run_at = Some(finished_at);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SchedulerState> {
+        // The lock is never held across anything that could panic, so the state is still valid.
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The scheduler is healthy while it runs, unless it hasn't had a successful run for more
+    /// than a day despite running long enough to have one.
+    fn check(&self, now: NaiveDateTime) -> (bool, SchedulerState) {
+        let state = self.lock().clone();
+        let last_success = state.last_successful_run_at.or(state.started_at);
+        let ok = state.running && last_success.is_some_and(|at| now - at <= DAILY_RUN_GRACE);
+        (ok, state)
+    }
+}
+
+pub struct RunningGuard(SchedulerStatus);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.lock().running = false;
+    }
+}
+
+/// Versions of migrations that haven't been applied, or why the database couldn't be checked.
+type DatabaseCheck = Result<Vec<i64>, String>;
+
+#[derive(Clone)]
+pub struct HealthState {
+    pool: Arc<PgPool>,
+    scheduler: SchedulerStatus,
+    tasks: TaskStatuses,
+    last_database_check: Arc<Mutex<Option<(Instant, DatabaseCheck)>>>,
+}
+
+impl HealthState {
+    pub fn new(pool: Arc<PgPool>, scheduler: SchedulerStatus, tasks: TaskStatuses) -> Self {
+        Self {
+            pool,
+            scheduler,
+            tasks,
+            last_database_check: Arc::default(),
+        }
+    }
+
+    /// Checks the database, or repeats the last result if it's recent enough.
+    async fn check_database(&self) -> DatabaseCheck {
+        let last_check = self
+            .last_database_check
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if let Some((checked_at, check)) = last_check {
+            if checked_at.elapsed() < DATABASE_CHECK_INTERVAL {
+                return check;
+            }
+        }
+
+        let check = pending_migrations(&self.pool).await;
+        *self
+            .last_database_check
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some((Instant::now(), check.clone()));
+        check
+    }
+}
+
+/// Routes for container probes and deployment checks. They need no token.
+pub fn router(state: HealthState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .route("/version", get(version))
+        .with_state(state)
+}
+
+/// The process is up and serving requests.
+async fn health() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Whether Root can do its job: the database is reachable, every migration has been applied,
+/// the daily task is running and no background task is waiting to be restarted. Responds with
+/// `503 Service Unavailable` otherwise. The body only says which checks failed, the details
+/// are logged.
+async fn ready(State(state): State<HealthState>) -> (StatusCode, Json<Value>) {
+    let database = state.check_database().await;
+
+    let now = Local::now().with_timezone(&timezone()).naive_local();
+    let (scheduler_ok, scheduler) = state.scheduler.check(now);
+
+    let tasks = state.tasks.snapshot();
+    let tasks_ok = tasks.values().all(|task| task.running);
+
+    let database_ok = database.is_ok();
+    let migrations_ok = database.as_ref().is_ok_and(|pending| pending.is_empty());
+    let ready = database_ok && migrations_ok && scheduler_ok && tasks_ok;
+
+    match &database {
+        Err(e) => warn!("Not ready, the database is unreachable: {}", e),
+        Ok(pending) if !pending.is_empty() => {
+            warn!("Not ready, migrations {:?} haven't been applied", pending)
+        }
+        Ok(_) => {}
+    }
+    if !scheduler_ok {
+        warn!(
+            "Not ready, the daily task is overdue or stopped. Running: {}, started at: {:?}, \
+            last successful run: {:?}",
+            scheduler.running, scheduler.started_at, scheduler.last_successful_run_at
+        );
+    }
+    for (task, status) in tasks.iter().filter(|(_, status)| !status.running) {
+        warn!(
+            "Not ready, {} is waiting to be restarted after {} crashes: {:?}",
+            task, status.restarts, status.last_error
+        );
+    }
+
+    let body = json!({
+        "status": if ready { "ready" } else { "unavailable" },
+        "checks": {
+            "database": { "ok": database_ok },
+            "migrations": { "ok": migrations_ok },
+            "scheduler": { "ok": scheduler_ok },
+            "tasks": { "ok": tasks_ok },
+        },
+    });
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(body))
+}
+
+/// Versions of migrations built into Root that haven't been applied to the database. Also
+/// tells whether the database is reachable at all.
+async fn pending_migrations(pool: &PgPool) -> DatabaseCheck {
+    let applied = tokio::time::timeout(
+        DATABASE_TIMEOUT,
+        sqlx::query_scalar::<_, i64>("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool),
+    )
+    .await
+    .map_err(|_| "Timed out".to_string())?
+    .map_err(|e| e.to_string())?;
+    let applied: HashSet<i64> = applied.into_iter().collect();
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
+
+/// The running build, see `build.rs`.
+async fn version() -> Json<Value> {
+    Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "commit": env!("ROOT_GIT_COMMIT"),
+        "builtAt": env!("ROOT_BUILD_TIME"),
+    }))
+}
@@ -0,0 +1,271 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use super::insert_member;
+use crate::{
+    csv,
+    models::{
+        member::{CreateMemberInput, Sex},
+        member_import::{ImportRowError, MemberImportReport},
+    },
+    validation::ValidationRules,
+};
+
+/// Columns of an import file, named like the `CreateMemberInput` fields.
+const COLUMNS: [&str; 9] = [
+    "rollNo",
+    "name",
+    "email",
+    "sex",
+    "year",
+    "hostel",
+    "macAddress",
+    "discordId",
+    "groupId",
+];
+
+/// Columns that must be unique across members, with the SQL column they're stored in.
+const UNIQUE_COLUMNS: [(&str, &str); 4] = [
+    ("rollNo", "roll_no"),
+    ("email", "email"),
+    ("macAddress", "mac_address"),
+    ("discordId", "discord_id"),
+];
+
+/// A row that passed validation.
+struct ValidRow {
+    row: i32,
+    input: CreateMemberInput,
+}
+
+impl ValidRow {
+    fn unique_value(&self, column: &str) -> &str {
+        match column {
+            "rollNo" => &self.input.roll_no,
+            "email" => &self.input.email,
+            "macAddress" => &self.input.mac_address,
+            _ => &self.input.discord_id,
+        }
+    }
+}
+
+fn row_error(row: i32, field: Option<&str>, message: impl Into<String>) -> ImportRowError {
+    ImportRowError {
+        row,
+        field: field.map(str::to_string),
+        message: message.into(),
+    }
+}
+
+/// Lower cases and drops underscores, so both `roll_no` and `rollNo` headers work.
+fn normalize_header(header: &str) -> String {
+    header.trim().replace('_', "").to_ascii_lowercase()
+}
+
+/// Imports members from CSV. Every row is validated and checked against existing members and
+/// the other rows before anything is written, and all rows are inserted in one transaction.
+pub async fn import_members(
+    csv: &str,
+    rules: &ValidationRules,
+    today: NaiveDate,
+    dry_run: bool,
+    pool: &PgPool,
+) -> Result<MemberImportReport, sqlx::Error> {
+    let failed = |errors| MemberImportReport {
+        dry_run,
+        imported: 0,
+        errors,
+        members: Vec::new(),
+    };
+
+    let records = match csv::parse(csv) {
+        Ok(records) => records,
+        Err(e) => return Ok(failed(vec![row_error(e.row as i32, None, e.message)])),
+    };
+    let Some((header, rows)) = records.split_first() else {
+        return Ok(failed(vec![row_error(1, None, "File is empty")]));
+    };
+
+    let header_row = header.row as i32;
+    let positions: HashMap<String, usize> = header
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (normalize_header(name), i))
+        .collect();
+    let missing: Vec<ImportRowError> = COLUMNS
+        .iter()
+        .filter(|column| !positions.contains_key(&normalize_header(column)))
+        .map(|column| row_error(header_row, Some(column), "Column is missing"))
+        .collect();
+    if !missing.is_empty() {
+        return Ok(failed(missing));
+    }
+
+    let mut errors = Vec::new();
+    let mut valid_rows = Vec::new();
+    for record in rows {
+        let row = record.row as i32;
+        let value = |column: &str| {
+            record
+                .fields
+                .get(positions[&normalize_header(column)])
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        match parse_row(row, value, rules) {
+            Ok(input) => valid_rows.push(ValidRow { row, input }),
+            Err(row_errors) => errors.extend(row_errors),
+        }
+    }
+
+    errors.extend(find_duplicates(&valid_rows, pool).await?);
+    errors.extend(find_unknown_groups(&valid_rows, pool).await?);
+    errors.sort_by_key(|error| error.row);
+
+    if !errors.is_empty() {
+        return Ok(failed(errors));
+    }
+    if dry_run {
+        return Ok(MemberImportReport {
+            dry_run,
+            imported: valid_rows.len() as i32,
+            errors,
+            members: Vec::new(),
+        });
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut members = Vec::with_capacity(valid_rows.len());
+    for valid_row in &valid_rows {
+        members.push(insert_member(&valid_row.input, today, &mut tx).await?);
+    }
+    tx.commit().await?;
+
+    Ok(MemberImportReport {
+        dry_run,
+        imported: members.len() as i32,
+        errors,
+        members,
+    })
+}
+
+fn parse_row(
+    row: i32,
+    value: impl Fn(&str) -> String,
+    rules: &ValidationRules,
+) -> Result<CreateMemberInput, Vec<ImportRowError>> {
+    let mut errors = Vec::new();
+
+    let sex = match value("sex").to_ascii_lowercase().as_str() {
+        "m" => Some(Sex::M),
+        "f" => Some(Sex::F),
+        "other" => Some(Sex::Other),
+        _ => {
+            errors.push(row_error(row, Some("sex"), "Sex must be M, F or Other"));
+            None
+        }
+    };
+    let mut parse_number = |column: &'static str| match value(column).parse::<i32>() {
+        Ok(number) => Some(number),
+        Err(_) => {
+            errors.push(row_error(row, Some(column), "Not a number"));
+            None
+        }
+    };
+    let year = parse_number("year");
+    let group_id = parse_number("groupId");
+
+    // Fields that couldn't be parsed get placeholders, so the rest of the row is still validated.
+    let input = CreateMemberInput {
+        roll_no: value("rollNo"),
+        name: value("name"),
+        email: value("email"),
+        sex: sex.unwrap_or(Sex::Other),
+        year: year.unwrap_or(1),
+        hostel: value("hostel"),
+        mac_address: value("macAddress"),
+        discord_id: value("discordId"),
+        group_id: group_id.unwrap_or_default(),
+    };
+
+    match input.validate(rules) {
+        Ok(input) if errors.is_empty() => Ok(input),
+        Ok(_) => Err(errors),
+        Err(validation_errors) => {
+            errors.extend(
+                validation_errors
+                    .0
+                    .into_iter()
+                    .map(|e| row_error(row, Some(e.field), e.message)),
+            );
+            Err(errors)
+        }
+    }
+}
+
+/// Reports rows whose unique columns repeat an earlier row or an existing member.
+async fn find_duplicates(
+    rows: &[ValidRow],
+    pool: &PgPool,
+) -> Result<Vec<ImportRowError>, sqlx::Error> {
+    let mut errors = Vec::new();
+
+    for (field, sql_column) in UNIQUE_COLUMNS {
+        let values: Vec<String> = rows
+            .iter()
+            .map(|row| row.unique_value(field).to_string())
+            .collect();
+        let existing: HashSet<String> = sqlx::query_scalar::<_, String>(&format!(
+            "SELECT {0} FROM Member WHERE {0} = ANY($1)",
+            sql_column
+        ))
+        .bind(&values)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let mut first_seen: HashMap<&str, i32> = HashMap::new();
+        for row in rows {
+            let value = row.unique_value(field);
+            if existing.contains(value) {
+                errors.push(row_error(
+                    row.row,
+                    Some(field),
+                    format!("A member with {} {} already exists", field, value),
+                ));
+            } else if let Some(first) = first_seen.get(value) {
+                errors.push(row_error(
+                    row.row,
+                    Some(field),
+                    format!("Same {} as row {}", field, first),
+                ));
+            } else {
+                first_seen.insert(value, row.row);
+            }
+        }
+    }
+
+    Ok(errors)
+}
+
+async fn find_unknown_groups(
+    rows: &[ValidRow],
+    pool: &PgPool,
+) -> Result<Vec<ImportRowError>, sqlx::Error> {
+    let groups: HashSet<i32> = sqlx::query_scalar::<_, i32>("SELECT group_id FROM MemberGroup")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+    Ok(rows
+        .iter()
+        .filter(|row| !groups.contains(&row.input.group_id))
+        .map(|row| row_error(row.row, Some("groupId"), "Group does not exist"))
+        .collect())
+}
@@ -0,0 +1,77 @@
+use chrono::NaiveDate;
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    events::{emit, Event},
+    models::member::{CreateMemberInput, Member},
+};
+
+pub mod import;
+pub mod rollover;
+
+/// Adds a member who joined on `joined_on`, along with the first entry of their status history.
+/// `input` is expected to be validated already.
+pub async fn insert_member(
+    input: &CreateMemberInput,
+    joined_on: NaiveDate,
+    conn: &mut PgConnection,
+) -> Result<Member, sqlx::Error> {
+    let member = sqlx::query_as::<_, Member>(
+        "INSERT INTO Member (roll_no, name, email, sex, year, hostel, mac_address, discord_id, group_id, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *",
+    )
+    .bind(&input.roll_no)
+    .bind(&input.name)
+    .bind(&input.email)
+    .bind(input.sex)
+    .bind(input.year)
+    .bind(&input.hostel)
+    .bind(&input.mac_address)
+    .bind(&input.discord_id)
+    .bind(input.group_id)
+    .bind(joined_on)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO MemberStatusChange (member_id, status, effective_from, reason, applied)
+        VALUES ($1, 'active', $2, 'Joined', TRUE)",
+    )
+    .bind(member.member_id)
+    .bind(member.status_since)
+    .execute(&mut *conn)
+    .await?;
+
+    emit(Event::MemberCreated(&member), &mut *conn).await?;
+    Ok(member)
+}
+
+/// Applies every status change that has become effective by `today`. If a member has
+/// several, the latest one wins. Returns the number of members whose status changed.
+pub async fn apply_due_status_changes(today: NaiveDate, pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let members = sqlx::query_as::<_, Member>(
+        "WITH due AS (
+            UPDATE MemberStatusChange SET applied = TRUE
+            WHERE NOT applied AND effective_from <= $1
+            RETURNING change_id, member_id, status, effective_from
+        ), latest AS (
+            SELECT DISTINCT ON (member_id) member_id, status, effective_from
+            FROM due ORDER BY member_id, effective_from DESC, change_id DESC
+        )
+        UPDATE Member m SET status = latest.status, status_since = latest.effective_from
+        FROM latest WHERE m.member_id = latest.member_id
+        RETURNING m.*",
+    )
+    .bind(today)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for member in &members {
+        emit(Event::MemberUpdated(member), &mut *tx).await?;
+    }
+
+    tx.commit().await?;
+    Ok(members.len() as u64)
+}
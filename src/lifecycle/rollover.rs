@@ -0,0 +1,245 @@
+use std::fmt;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use sqlx::PgPool;
+
+use crate::{
+    events::{emit, Event},
+    models::{
+        member::Member,
+        rollover::{RolloverChange, RolloverOutcome, YearRollover},
+    },
+};
+
+/// Members in this year graduate instead of moving up.
+pub const FINAL_YEAR: i32 = 4;
+
+/// When the academic year rolls over and for how long a rollover can be undone.
+#[derive(Clone)]
+pub struct RolloverRules {
+    /// Month and day on which the daily task rolls over the academic year. `None` leaves it to admins.
+    pub date: Option<(u32, u32)>,
+    pub undo_window: Duration,
+}
+
+impl RolloverRules {
+    pub fn is_rollover_day(&self, date: NaiveDate) -> bool {
+        self.date == Some((date.month(), date.day()))
+    }
+}
+
+#[derive(Debug)]
+pub enum RolloverError {
+    AlreadyRolledOver(i32),
+    NotFound,
+    AlreadyUndone,
+    /// The undo window closed at the given time.
+    UndoWindowClosed(NaiveDateTime),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for RolloverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RolloverError::AlreadyRolledOver(year) => {
+                write!(f, "Academic year {} has already been rolled over", year)
+            }
+            RolloverError::NotFound => write!(f, "Rollover not found"),
+            RolloverError::AlreadyUndone => write!(f, "Rollover has already been undone"),
+            RolloverError::UndoWindowClosed(until) => {
+                write!(f, "Rollover could only be undone until {}", until)
+            }
+            RolloverError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RolloverError {}
+
+impl From<sqlx::Error> for RolloverError {
+    fn from(e: sqlx::Error) -> Self {
+        RolloverError::Database(e)
+    }
+}
+
+/// Moves every active member up a year, and final years to alumni, effective `now`.
+/// With `dry_run`, nothing is written and the outcome only shows what would change.
+pub async fn roll_over(
+    academic_year: i32,
+    now: NaiveDateTime,
+    scheduled: bool,
+    dry_run: bool,
+    pool: &PgPool,
+) -> Result<RolloverOutcome, RolloverError> {
+    let today = now.date();
+    let mut tx = pool.begin().await?;
+
+    let existing = sqlx::query_scalar::<_, i32>(
+        "SELECT rollover_id FROM YearRollover WHERE academic_year = $1 AND undone_at IS NULL",
+    )
+    .bind(academic_year)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if existing.is_some() {
+        return Err(RolloverError::AlreadyRolledOver(academic_year));
+    }
+
+    let changes = sqlx::query_as::<_, RolloverChange>(
+        "SELECT member_id, roll_no, name, year AS previous_year,
+            LEAST(year + 1, $1) AS new_year, year >= $1 AS graduated
+        FROM Member WHERE status = 'active' ORDER BY year DESC, name
+        FOR UPDATE",
+    )
+    .bind(FINAL_YEAR)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let graduated = changes.iter().filter(|change| change.graduated).count() as i32;
+    let promoted = changes.len() as i32 - graduated;
+
+    if dry_run {
+        return Ok(RolloverOutcome {
+            academic_year,
+            dry_run,
+            promoted,
+            graduated,
+            changes,
+            rollover: None,
+        });
+    }
+
+    let rollover = sqlx::query_as::<_, YearRollover>(
+        "INSERT INTO YearRollover (academic_year, scheduled, promoted, graduated, performed_at)
+        VALUES ($1, $2, $3, $4, $5) RETURNING *",
+    )
+    .bind(academic_year)
+    .bind(scheduled)
+    .bind(promoted)
+    .bind(graduated)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for change in &changes {
+        let status_change_id = if change.graduated {
+            let change_id = sqlx::query_scalar::<_, i32>(
+                "INSERT INTO MemberStatusChange (member_id, status, effective_from, reason, applied)
+                VALUES ($1, 'alumni', $2, $3, TRUE) RETURNING change_id",
+            )
+            .bind(change.member_id)
+            .bind(today)
+            .bind(format!("Graduated in the {} rollover", academic_year))
+            .fetch_one(&mut *tx)
+            .await?;
+            Some(change_id)
+        } else {
+            None
+        };
+
+        sqlx::query(
+            "INSERT INTO YearRolloverEntry
+                (rollover_id, member_id, previous_year, new_year, graduated, previous_status_since, status_change_id)
+            SELECT $1, member_id, $2, $3, $4, status_since, $5 FROM Member WHERE member_id = $6",
+        )
+        .bind(rollover.rollover_id)
+        .bind(change.previous_year)
+        .bind(change.new_year)
+        .bind(change.graduated)
+        .bind(status_change_id)
+        .bind(change.member_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let members = sqlx::query_as::<_, Member>(
+        "UPDATE Member m SET
+            year = e.new_year,
+            status = CASE WHEN e.graduated THEN 'alumni'::member_status ELSE m.status END,
+            status_since = CASE WHEN e.graduated THEN $2 ELSE m.status_since END
+        FROM YearRolloverEntry e
+        WHERE e.rollover_id = $1 AND e.member_id = m.member_id
+        RETURNING m.*",
+    )
+    .bind(rollover.rollover_id)
+    .bind(today)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for member in &members {
+        emit(Event::MemberUpdated(member), &mut *tx).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(RolloverOutcome {
+        academic_year,
+        dry_run,
+        promoted,
+        graduated,
+        changes,
+        rollover: Some(rollover),
+    })
+}
+
+/// Puts members back in the year and status they had before a rollover. Graduates
+/// whose status was changed again since the rollover keep their current status.
+pub async fn undo_rollover(
+    rollover_id: i32,
+    now: NaiveDateTime,
+    rules: &RolloverRules,
+    pool: &PgPool,
+) -> Result<YearRollover, RolloverError> {
+    let mut tx = pool.begin().await?;
+
+    let rollover = sqlx::query_as::<_, YearRollover>(
+        "SELECT * FROM YearRollover WHERE rollover_id = $1 FOR UPDATE",
+    )
+    .bind(rollover_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(RolloverError::NotFound)?;
+
+    if rollover.undone_at.is_some() {
+        return Err(RolloverError::AlreadyUndone);
+    }
+    let undoable_until = rollover.performed_at + rules.undo_window;
+    if now > undoable_until {
+        return Err(RolloverError::UndoWindowClosed(undoable_until));
+    }
+
+    let members = sqlx::query_as::<_, Member>(
+        "UPDATE Member m SET
+            year = e.previous_year,
+            status = CASE WHEN e.graduated AND m.status = 'alumni' THEN 'active'::member_status ELSE m.status END,
+            status_since = CASE WHEN e.graduated AND m.status = 'alumni' THEN e.previous_status_since ELSE m.status_since END
+        FROM YearRolloverEntry e
+        WHERE e.rollover_id = $1 AND e.member_id = m.member_id
+        RETURNING m.*",
+    )
+    .bind(rollover_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for member in &members {
+        emit(Event::MemberUpdated(member), &mut *tx).await?;
+    }
+
+    sqlx::query(
+        "DELETE FROM MemberStatusChange WHERE change_id IN
+            (SELECT status_change_id FROM YearRolloverEntry WHERE rollover_id = $1)",
+    )
+    .bind(rollover_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let rollover = sqlx::query_as::<_, YearRollover>(
+        "UPDATE YearRollover SET undone_at = $2 WHERE rollover_id = $1 RETURNING *",
+    )
+    .bind(rollover_id)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(rollover)
+}
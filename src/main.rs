@@ -1,113 +1,273 @@
 use async_graphql::EmptySubscription;
 use axum::http::{HeaderValue, Method};
 use sqlx::PgPool;
-use std::sync::Arc;
+use std::{future::IntoFuture, net::SocketAddr, sync::Arc};
 use time::UtcOffset;
 use tower_http::cors::CorsLayer;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+use auth::Authenticator;
+use chrono::Offset;
+use config::{Config, DatabaseConfig};
+use contributions::WebhookState;
 use daily_task::run_daily_task_at_midnight;
-use graphql::{Mutation, Query};
+use email::{digest::Digests, EmailState, Mailer, SmtpMailer, SmtpSettings, UnsubscribeLinks};
+use events::delivery::run_webhook_dispatcher;
+use graphql::{
+    limits::{LimitErrorCodes, QueryLimits},
+    metrics::OperationMetrics,
+    Mutation, Query,
+};
+use health::{HealthState, SchedulerStatus};
+use lifecycle::rollover::RolloverRules;
+use metrics::{Metrics, MetricsState};
+use notifier::{discord::HttpDiscordClient, Notifier};
+use project_activity::{github::GitHubClient, ForgeClients};
+use rate_limit::RateLimitLayer;
+use reports::ReportRules;
 use routes::setup_router;
+use streaks::StreakRules;
+use supervisor::{shutdown_signal, Shutdown, Supervisor};
+use validation::ValidationRules;
 
+pub mod auth;
+pub mod cli;
+pub mod config;
+pub mod contributions;
+pub mod csv;
 pub mod daily_task;
+pub mod email;
+pub mod events;
+pub mod export;
 pub mod graphql;
+pub mod health;
+pub mod lifecycle;
+pub mod metrics;
 pub mod models;
+pub mod notifier;
+pub mod project_activity;
+pub mod rate_limit;
+pub mod reports;
 pub mod routes;
+pub mod streaks;
+pub mod supervisor;
+pub mod validation;
 
-/// Handles all over environment variables in one place.
-// TODO: Replace with `Config.rs` crate.
-struct Config {
-    env: String,
-    secret_key: String,
-    database_url: String,
-    port: String,
-}
+/// Migrations built into the binary, applied at startup.
+pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!();
 
-impl Config {
-    fn from_env() -> Self {
-        let _ = dotenv::dotenv();
-        Self {
-            env: std::env::var("ROOT_ENV").unwrap_or_else(|_| "development".to_string()),
-            secret_key: std::env::var("ROOT_SECRET").expect("ROOT_SECRET must be set."),
-            database_url: std::env::var("DATABASE_URL").expect("DATABASE_URL must be set."),
-            port: std::env::var("ROOT_PORT").expect("ROOT_PORT must be set."),
-        }
-    }
-}
+/// Downloads that can run at once, see `setup_export_pool`.
+const EXPORT_MAX_CONNECTIONS: u32 = 2;
 
 #[tokio::main]
 async fn main() {
-    let config = Config::from_env();
-    setup_tracing(&config.env);
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprint!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    config.apply_timezone();
+
+    // Any arguments run a one-off command, see `cli.rs`.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let pool = setup_database(&config.database).await;
+        if let Err(e) = cli::run(&args, &pool, &config.validation_rules).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    let pool = setup_database(&config.database_url).await;
-    let schema = build_graphql_schema(pool.clone(), config.secret_key);
+    setup_tracing(&config);
 
-    tokio::task::spawn(async {
-        run_daily_task_at_midnight(pool).await;
+    let pool = setup_database(&config.database).await;
+    let metrics = Metrics::new();
+    let streak_rules = config.streak_rules;
+    let rollover_rules = config.rollover_rules;
+    let report_rules = config.report_rules;
+    let notifier = Notifier::new(
+        Arc::new(HttpDiscordClient::default()),
+        config.streak_milestones,
+        report_rules.late_after,
+        streak_rules.clone(),
+    );
+    let unsubscribe_links = config
+        .public_url
+        .zip(config.unsubscribe_secret)
+        .map(|(base_url, secret)| UnsubscribeLinks { base_url, secret });
+    let digests = Digests::new(
+        setup_mailer(config.smtp),
+        config.mail_from,
+        config.digest_day,
+        unsubscribe_links.clone(),
+    );
+    let forge_clients = ForgeClients::new(vec![Arc::new(GitHubClient::new(
+        config.github_api_url,
+        config.github_token,
+    ))]);
+    let schema = build_graphql_schema(
+        pool.clone(),
+        config.secret_key.clone(),
+        config.validation_rules,
+        config.query_limits,
+        streak_rules.clone(),
+        rollover_rules.clone(),
+        report_rules.clone(),
+        notifier.clone(),
+        digests.clone(),
+        forge_clients.clone(),
+        metrics.clone(),
+    );
+    let webhook_state = WebhookState {
+        pool: pool.clone(),
+        streak_rules: streak_rules.clone(),
+        metrics: metrics.clone(),
+    };
+    let scheduler = SchedulerStatus::default();
+    let shutdown = Shutdown::default();
+    let mut supervisor = Supervisor::new(shutdown.clone(), metrics.clone());
+    let health_state = HealthState::new(pool.clone(), scheduler.clone(), supervisor.statuses());
+    let email_state = EmailState {
+        pool: pool.clone(),
+        links: unsubscribe_links,
+        metrics: metrics.clone(),
+    };
+    let metrics_state = MetricsState {
+        pool: pool.clone(),
+        metrics: metrics.clone(),
+        token: config.metrics_token,
+    };
+
+    let export_pool = setup_export_pool(&config.database);
+    let dispatcher_pool = pool.clone();
+    supervisor.spawn("webhook_dispatcher", move |shutdown| {
+        run_webhook_dispatcher(
+            dispatcher_pool.clone(),
+            config.delivery_rules.clone(),
+            shutdown,
+        )
+    });
+    let daily_task_pool = pool.clone();
+    let daily_task_metrics = metrics.clone();
+    let daily_task_at = config.daily_task_at;
+    supervisor.spawn("daily_task", move |shutdown| {
+        run_daily_task_at_midnight(
+            daily_task_at,
+            daily_task_pool.clone(),
+            streak_rules.clone(),
+            rollover_rules.clone(),
+            report_rules.clone(),
+            notifier.clone(),
+            digests.clone(),
+            forge_clients.clone(),
+            scheduler.clone(),
+            daily_task_metrics.clone(),
+            shutdown,
+        )
     });
 
-    let cors = setup_cors();
-    let router = setup_router(schema, cors, config.env == "development");
+    let cors = setup_cors(config.cors_origins);
+    let rate_limit = RateLimitLayer::new(config.rate_limit);
+    let router = setup_router(
+        schema,
+        Authenticator::new(config.member_token_secret, config.admin_token, metrics),
+        export_pool,
+        cors,
+        rate_limit,
+        webhook_state,
+        email_state,
+        health_state,
+        metrics_state,
+        config.env == "development",
+    );
 
     info!("Starting Root...");
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", config.port))
+    let listener = tokio::net::TcpListener::bind((config.host.as_str(), config.port))
         .await
         .unwrap();
-    axum::serve(listener, router).await.unwrap();
-}
+    // Peer addresses are needed to identify clients for rate limiting.
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown({
+        let shutdown = shutdown.clone();
+        async move { shutdown.requested().await }
+    });
+    let mut server = tokio::spawn(server.into_future());
 
-fn setup_tracing(env: &str) {
-    let kolkata_offset = UtcOffset::from_hms(5, 30, 0).expect("Hardcoded offset must be correct");
-    let timer = fmt::time::OffsetTime::new(
-        kolkata_offset,
-        time::format_description::well_known::Rfc2822,
-    );
-    if env == "production" {
-        tracing_subscriber::registry()
-            // Don't waste resources writing to unmonitored stdout in production
-            .with(
-                fmt::layer()
-                    .event_format(fmt::format().with_timer(timer.clone()))
-                    .pretty()
-                    .with_ansi(false) // ANSI encodings are unreadable in the raw file.
-                    .with_writer(std::fs::File::create("root.log").unwrap()),
-            )
-            .with(EnvFilter::new("info"))
-            .init();
-        info!("Running in production mode.")
-    } else {
-        tracing_subscriber::registry()
-            .with(
-                fmt::layer()
-                    .event_format(fmt::format().with_timer(timer.clone()))
-                    .pretty()
-                    .with_writer(std::io::stdout),
-            )
-            .with(
-                fmt::layer()
-                    .event_format(fmt::format().with_timer(timer.clone()))
-                    .pretty()
-                    .with_ansi(false)
-                    .with_writer(std::fs::File::create("root.log").unwrap()),
-            )
-            .with(EnvFilter::new("trace"))
-            .init();
-        info!("Running in development mode.");
+    shutdown_signal().await;
+    info!("Shutting down, waiting for in-flight requests...");
+    shutdown.trigger();
+
+    // Requests and background tasks share the deadline, so slow clients can't hold it up.
+    let deadline = tokio::time::Instant::now() + config.shutdown_timeout;
+    match tokio::time::timeout_at(deadline, &mut server).await {
+        Ok(result) => result
+            .expect("Server task mustn't panic")
+            .expect("Server must not fail"),
+        Err(_) => {
+            warn!(
+                "In-flight requests didn't finish within {}s, dropping them",
+                config.shutdown_timeout.as_secs()
+            );
+            server.abort();
+        }
     }
+
+    supervisor.wait_until(deadline).await;
+    pool.close().await;
+    info!("Root stopped.");
+}
+
+fn setup_tracing(config: &Config) {
+    let now = chrono::Utc::now().with_timezone(&config.timezone);
+    let offset = UtcOffset::from_whole_seconds(now.offset().fix().local_minus_utc())
+        .expect("Timezone offsets must be within a day");
+    let timer = fmt::time::OffsetTime::new(offset, time::format_description::well_known::Rfc2822);
+    let file = config.logging.file.as_ref().map(|path| {
+        fmt::layer()
+            .event_format(fmt::format().with_timer(timer.clone()))
+            .pretty()
+            .with_ansi(false) // ANSI encodings are unreadable in the raw file.
+            .with_writer(std::fs::File::create(path).expect("Log file must be writable."))
+    });
+    // Don't waste resources writing to unmonitored stdout in production, unless there's no file.
+    let stdout = (config.env != "production" || file.is_none()).then(|| {
+        fmt::layer()
+            .event_format(fmt::format().with_timer(timer))
+            .pretty()
+            .with_writer(std::io::stdout)
+    });
+
+    tracing_subscriber::registry()
+        .with(file)
+        .with(stdout)
+        .with(EnvFilter::new(&config.logging.level))
+        .init();
+    info!("Running in {} mode.", config.env);
 }
 
-async fn setup_database(database_url: &str) -> Arc<PgPool> {
+/// Sending email is disabled unless an SMTP host is configured.
+fn setup_mailer(settings: Option<SmtpSettings>) -> Option<Arc<dyn Mailer>> {
+    let mailer = SmtpMailer::new(settings?).expect("SMTP transport must be built properly.");
+    Some(Arc::new(mailer))
+}
+
+async fn setup_database(config: &DatabaseConfig) -> Arc<PgPool> {
     let pool = sqlx::postgres::PgPoolOptions::new()
-        .min_connections(2)
-        .max_connections(3)
-        .connect(database_url)
+        .min_connections(config.min_connections)
+        .max_connections(config.max_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .connect(&config.url)
         .await
         .expect("Pool must be initialized properly.");
 
-    sqlx::migrate!()
+    MIGRATOR
         .run(&pool)
         .await
         .expect("Failed to run migrations.");
@@ -115,23 +275,51 @@ async fn setup_database(database_url: &str) -> Arc<PgPool> {
     Arc::new(pool)
 }
 
+/// Exports hold a connection for as long as a download takes, so they get their own
+/// connections instead of starving GraphQL requests of the shared pool.
+fn setup_export_pool(config: &DatabaseConfig) -> Arc<PgPool> {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(EXPORT_MAX_CONNECTIONS)
+        .acquire_timeout(config.acquire_timeout)
+        .connect_lazy(&config.url)
+        .expect("Export pool must be initialized properly.");
+
+    Arc::new(pool)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn build_graphql_schema(
     pool: Arc<PgPool>,
     secret_key: String,
+    validation_rules: ValidationRules,
+    limits: QueryLimits,
+    streak_rules: StreakRules,
+    rollover_rules: RolloverRules,
+    report_rules: ReportRules,
+    notifier: Notifier,
+    digests: Digests,
+    forge_clients: ForgeClients,
+    metrics: Metrics,
 ) -> async_graphql::Schema<Query, Mutation, EmptySubscription> {
     async_graphql::Schema::build(Query::default(), Mutation::default(), EmptySubscription)
         .data(pool)
         .data(secret_key)
+        .data(validation_rules)
+        .data(streak_rules)
+        .data(rollover_rules)
+        .data(report_rules)
+        .data(notifier)
+        .data(digests)
+        .data(forge_clients)
+        .data(metrics.clone())
+        .limit_depth(limits.max_depth)
+        .limit_complexity(limits.max_complexity)
+        .extension(LimitErrorCodes(limits))
+        .extension(OperationMetrics(metrics))
         .finish()
 }
 
-fn setup_cors() -> CorsLayer {
-    // TODO: Replace hardcoded strings
-    let origins: [HeaderValue; 2] = [
-        "http://127.0.0.1:3000".parse().unwrap(),
-        "https://home.amfoss.in".parse().unwrap(),
-    ];
-
+fn setup_cors(origins: Vec<HeaderValue>) -> CorsLayer {
     CorsLayer::new()
         .allow_origin(origins)
         .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
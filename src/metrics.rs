@@ -0,0 +1,272 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+use crate::auth::constant_time_eq;
+
+/// Something checked with an HMAC, for counting failed checks.
+#[derive(Clone, Copy, Debug)]
+pub enum HmacCheck {
+    MemberToken,
+    Attendance,
+    ForgeWebhook,
+    Unsubscribe,
+}
+
+impl HmacCheck {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HmacCheck::MemberToken => "member_token",
+            HmacCheck::Attendance => "attendance",
+            HmacCheck::ForgeWebhook => "forge_webhook",
+            HmacCheck::Unsubscribe => "unsubscribe",
+        }
+    }
+}
+
+/// Prometheus metrics of the running process, served at `/metrics`. Cloning shares the metrics.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    graphql_operations: IntCounterVec,
+    graphql_duration: HistogramVec,
+    graphql_errors: IntCounterVec,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+    daily_task_runs: IntCounterVec,
+    daily_task_duration: Histogram,
+    attendance_marks: IntCounterVec,
+    hmac_failures: IntCounterVec,
+    task_restarts: IntCounterVec,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("root".to_string()), None)
+            .expect("Hardcoded prefix must be valid");
+
+        let metrics = Self {
+            graphql_operations: IntCounterVec::new(
+                Opts::new(
+                    "graphql_operations_total",
+                    "GraphQL requests by the root fields they select",
+                ),
+                &["operation"],
+            )
+            .expect("Metric must be valid"),
+            graphql_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "graphql_operation_duration_seconds",
+                    "Time taken to answer GraphQL requests by the root fields they select",
+                ),
+                &["operation"],
+            )
+            .expect("Metric must be valid"),
+            graphql_errors: IntCounterVec::new(
+                Opts::new(
+                    "graphql_errors_total",
+                    "Errors in GraphQL responses by their code",
+                ),
+                &["code"],
+            )
+            .expect("Metric must be valid"),
+            pool_connections: IntGaugeVec::new(
+                Opts::new(
+                    "db_pool_connections",
+                    "Open database connections by whether they are in use",
+                ),
+                &["state"],
+            )
+            .expect("Metric must be valid"),
+            pool_max_connections: IntGauge::new(
+                "db_pool_max_connections",
+                "Connections the database pool may open",
+            )
+            .expect("Metric must be valid"),
+            daily_task_runs: IntCounterVec::new(
+                Opts::new("daily_task_runs_total", "Daily task runs by outcome"),
+                &["outcome"],
+            )
+            .expect("Metric must be valid"),
+            daily_task_duration: Histogram::with_opts(
+                HistogramOpts::new(
+                    "daily_task_duration_seconds",
+                    "Time taken by the daily task",
+                )
+                .buckets(vec![
+                    1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+                ]),
+            )
+            .expect("Metric must be valid"),
+            attendance_marks: IntCounterVec::new(
+                Opts::new(
+                    "attendance_marks_total",
+                    "Attendance marked by hour of the day, in the configured timezone",
+                ),
+                &["hour"],
+            )
+            .expect("Metric must be valid"),
+            hmac_failures: IntCounterVec::new(
+                Opts::new(
+                    "hmac_verification_failures_total",
+                    "Rejected HMAC signatures by what was signed",
+                ),
+                &["kind"],
+            )
+            .expect("Metric must be valid"),
+            task_restarts: IntCounterVec::new(
+                Opts::new(
+                    "background_task_restarts_total",
+                    "Background tasks restarted after crashing, by task",
+                ),
+                &["task"],
+            )
+            .expect("Metric must be valid"),
+            registry,
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 10] = [
+            Box::new(metrics.graphql_operations.clone()),
+            Box::new(metrics.graphql_duration.clone()),
+            Box::new(metrics.graphql_errors.clone()),
+            Box::new(metrics.pool_connections.clone()),
+            Box::new(metrics.pool_max_connections.clone()),
+            Box::new(metrics.daily_task_runs.clone()),
+            Box::new(metrics.daily_task_duration.clone()),
+            Box::new(metrics.attendance_marks.clone()),
+            Box::new(metrics.hmac_failures.clone()),
+            Box::new(metrics.task_restarts.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("Metrics must only be registered once");
+        }
+        metrics
+    }
+
+    /// Records an answered GraphQL request under each root field it selected, or `other` if
+    /// it didn't select any, along with the codes of its errors. Errors without a code are
+    /// counted as `UNSPECIFIED`.
+    pub fn record_graphql_request<'a>(
+        &self,
+        root_fields: &[String],
+        duration: Duration,
+        error_codes: impl IntoIterator<Item = Option<&'a str>>,
+    ) {
+        let other = ["other".to_string()];
+        let labels = if root_fields.is_empty() {
+            &other[..]
+        } else {
+            root_fields
+        };
+        for label in labels {
+            self.graphql_operations.with_label_values(&[label]).inc();
+            self.graphql_duration
+                .with_label_values(&[label])
+                .observe(duration.as_secs_f64());
+        }
+        for code in error_codes {
+            self.graphql_errors
+                .with_label_values(&[code.unwrap_or("UNSPECIFIED")])
+                .inc();
+        }
+    }
+
+    pub fn record_daily_task(&self, duration: Duration, succeeded: bool) {
+        let outcome = if succeeded { "success" } else { "failure" };
+        self.daily_task_runs.with_label_values(&[outcome]).inc();
+        self.daily_task_duration.observe(duration.as_secs_f64());
+    }
+
+    /// Records attendance marked during `hour` of the day.
+    pub fn record_attendance_mark(&self, hour: u32) {
+        self.attendance_marks
+            .with_label_values(&[&format!("{:02}", hour)])
+            .inc();
+    }
+
+    pub fn record_hmac_failure(&self, check: HmacCheck) {
+        self.hmac_failures
+            .with_label_values(&[check.as_str()])
+            .inc();
+    }
+
+    pub fn record_task_restart(&self, task: &str) {
+        self.task_restarts.with_label_values(&[task]).inc();
+    }
+
+    #[cfg(test)]
+    pub fn graphql_requests(&self, label: &str) -> u64 {
+        self.graphql_operations.with_label_values(&[label]).get()
+    }
+
+    /// Every metric in Prometheus' text format, with the pool's current utilization.
+    fn render(&self, pool: &PgPool) -> String {
+        let idle = pool.num_idle() as i64;
+        self.pool_connections.with_label_values(&["idle"]).set(idle);
+        self.pool_connections
+            .with_label_values(&["active"])
+            .set(pool.size() as i64 - idle);
+        self.pool_max_connections
+            .set(pool.options().get_max_connections() as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Metrics must encode as text");
+        String::from_utf8(buffer).expect("Prometheus' text format is UTF-8")
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsState {
+    pub pool: Arc<PgPool>,
+    pub metrics: Metrics,
+    /// Scrapers must send `Authorization: Bearer <token>` if set.
+    pub token: Option<String>,
+}
+
+/// The route Prometheus scrapes.
+pub fn router(state: MetricsState) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .with_state(state)
+}
+
+async fn metrics(State(state): State<MetricsState>, headers: HeaderMap) -> Response {
+    if let Some(token) = &state.token {
+        let authorized = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|sent| constant_time_eq(sent.trim().as_bytes(), token.as_bytes()));
+        if !authorized {
+            return (StatusCode::UNAUTHORIZED, "Invalid metrics token").into_response();
+        }
+    }
+
+    (
+        [(header::CONTENT_TYPE, TextEncoder::new().format_type())],
+        state.metrics.render(&state.pool),
+    )
+        .into_response()
+}
@@ -1,8 +1,10 @@
 use async_graphql::{InputObject, SimpleObject};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
 use sqlx::FromRow;
 
-#[derive(SimpleObject, FromRow)]
+#[derive(SimpleObject, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Attendance {
     pub attendance_id: i32,
     pub member_id: i32,
@@ -11,8 +13,10 @@ pub struct Attendance {
     pub time_in: Option<NaiveTime>,
     pub time_out: Option<NaiveTime>,
     #[graphql(skip)] // Don't expose internal fields/meta-data
+    #[serde(skip)]
     pub created_at: NaiveDateTime,
     #[graphql(skip)]
+    #[serde(skip)]
     pub updated_at: NaiveDateTime,
 }
 
@@ -0,0 +1,69 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "contribution_kind", rename_all = "snake_case")]
+pub enum ContributionKind {
+    Commit,
+    PullRequestOpened,
+    PullRequestMerged,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct Contribution {
+    pub contribution_id: i32,
+    pub member_id: i32,
+    pub project_id: Option<i32>,
+    pub kind: ContributionKind,
+    /// `owner/name` of the repository.
+    pub repository: String,
+    /// Commit SHA or pull request number.
+    pub reference: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub contributed_at: NaiveDateTime,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct ForgeAccount {
+    pub member_id: i32,
+    pub host: String,
+    pub username: String,
+    /// Contributions are only credited once an admin has approved the account.
+    pub approved: bool,
+}
+
+#[derive(InputObject)]
+pub struct ForgeAccountInput {
+    pub member_id: i32,
+    /// e.g. `github.com`
+    pub host: String,
+    pub username: String,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct ForgeWebhook {
+    pub hook_id: i32,
+    pub project_id: Option<i32>,
+    #[graphql(skip)]
+    pub secret: String,
+    pub counts_as_status_update: bool,
+    #[graphql(skip)]
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(InputObject)]
+pub struct CreateForgeWebhookInput {
+    /// Contributions are credited to this project. If left out, the project is
+    /// looked up by the repository URL in each payload.
+    pub project_id: Option<i32>,
+    /// Shared with the forge and used to verify payload signatures.
+    #[graphql(validator(min_length = 16))]
+    pub secret: String,
+    /// Whether a contribution also counts as the member's status update for that day.
+    #[graphql(default)]
+    pub counts_as_status_update: bool,
+}
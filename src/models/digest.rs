@@ -0,0 +1,26 @@
+use async_graphql::SimpleObject;
+use chrono::NaiveDate;
+
+/// The weekly email sent to a group's mentor.
+#[derive(SimpleObject)]
+pub struct GroupDigest {
+    pub group_id: i32,
+    /// First day covered by the digest.
+    pub from: NaiveDate,
+    /// Last day covered by the digest.
+    pub to: NaiveDate,
+    /// The mentor's address, null if the group has no mentor.
+    pub recipient: Option<String>,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    #[graphql(skip)]
+    pub mentor_id: Option<i32>,
+    #[graphql(skip)]
+    pub mentor_name: Option<String>,
+    /// Where the mentor can opt out, null without a mentor or `ROOT_PUBLIC_URL`.
+    #[graphql(skip)]
+    pub unsubscribe_url: Option<String>,
+    /// Active members of the group covered by the digest.
+    pub members: i32,
+}
@@ -0,0 +1,53 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, sqlx::Type)]
+#[sqlx(type_name = "notification_kind", rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// Active members who weren't in the lab on a working day.
+    DailyAbsentees,
+    /// Members who checked in after `ROOT_LATE_AFTER`.
+    LateCheckIns,
+    /// Members whose status update streak reached one of `ROOT_STREAK_MILESTONES`.
+    StreakMilestones,
+    /// Members who missed the last due status update after keeping a streak.
+    StreaksBroken,
+}
+
+/// A Discord channel notifications can be posted to.
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct DiscordChannel {
+    pub channel_id: i32,
+    pub name: String,
+    /// Contains the webhook's token.
+    #[graphql(skip)]
+    pub webhook_url: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(InputObject)]
+pub struct CreateDiscordChannelInput {
+    pub name: String,
+    /// The channel's webhook URL, from its Integrations settings in Discord.
+    #[graphql(validator(url))]
+    pub webhook_url: String,
+}
+
+/// Sends a kind of notification to a channel.
+#[derive(SimpleObject, FromRow)]
+pub struct DiscordRoute {
+    pub channel_id: i32,
+    pub kind: NotificationKind,
+    /// Handlebars template for the message. The default template is used if null.
+    pub template: Option<String>,
+}
+
+#[derive(InputObject)]
+pub struct SetDiscordRouteInput {
+    pub channel_id: i32,
+    pub kind: NotificationKind,
+    /// Leave out to use the default template.
+    pub template: Option<String>,
+}
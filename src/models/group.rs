@@ -0,0 +1,54 @@
+use async_graphql::{InputObject, MaybeUndefined, SimpleObject};
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct Group {
+    pub group_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub mentor_id: Option<i32>,
+    /// Free form, e.g. "Saturday 16:00".
+    pub meeting_slot: Option<String>,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(InputObject)]
+pub struct CreateGroupInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub mentor_id: Option<i32>,
+    pub meeting_slot: Option<String>,
+}
+
+/// Fields left out are not changed, `null` clears them.
+#[derive(InputObject)]
+pub struct UpdateGroupInput {
+    pub group_id: i32,
+    pub name: Option<String>,
+    pub description: MaybeUndefined<String>,
+    pub mentor_id: MaybeUndefined<i32>,
+    pub meeting_slot: MaybeUndefined<String>,
+}
+
+/// Attendance of a group's members during one month.
+#[derive(SimpleObject, FromRow)]
+pub struct GroupAttendance {
+    pub year: i32,
+    pub month: i32,
+    pub member_count: i64,
+    pub total_days_attended: i64,
+    pub average_days_attended: f64,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct GroupStreaks {
+    pub member_count: i64,
+    /// Members whose current streak is above 0.
+    pub active_streaks: i64,
+    pub average_current_streak: f64,
+    pub longest_current_streak: i32,
+    pub longest_max_streak: i32,
+}
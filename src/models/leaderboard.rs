@@ -0,0 +1,35 @@
+use async_graphql::{Enum, SimpleObject};
+use sqlx::FromRow;
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum LeaderboardKind {
+    CurrentStreak,
+    MaxStreak,
+    DaysAttended,
+    HoursInLab,
+    /// Days attended + status updates posted + a quarter of the hours spent in the lab.
+    ActivityScore,
+}
+
+/// How far back attendance and status updates are counted. Streaks always use their current values.
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum LeaderboardPeriod {
+    /// The last 7 days, including today.
+    Week,
+    /// Since the first of this month.
+    Month,
+    /// Since the first of January.
+    Year,
+    AllTime,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct LeaderboardEntry {
+    /// Members with the same score share a rank, and the next rank is skipped.
+    pub rank: i64,
+    pub member_id: i32,
+    pub name: String,
+    pub year: i32,
+    pub group_id: i32,
+    pub score: f64,
+}
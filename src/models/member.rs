@@ -1,29 +1,50 @@
 use async_graphql::{Enum, InputObject, SimpleObject};
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
 use sqlx::FromRow;
 
-#[derive(Enum, Copy, Clone, Eq, PartialEq, sqlx::Type)]
+#[derive(Enum, Copy, Clone, Eq, PartialEq, sqlx::Type, Serialize)]
 #[sqlx(type_name = "sex_type")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Sex {
     M,
     F,
     Other,
 }
 
-#[derive(SimpleObject, FromRow)]
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, sqlx::Type, Serialize)]
+#[sqlx(type_name = "member_status", rename_all = "snake_case")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MemberStatus {
+    Active,
+    OnBreak,
+    Inactive,
+    Alumni,
+}
+
+#[derive(SimpleObject, FromRow, Serialize)]
 #[graphql(complex)]
+#[serde(rename_all = "camelCase")]
 pub struct Member {
     pub member_id: i32,
     pub roll_no: String,
     pub name: String,
+    #[graphql(skip)] // Resolved in member_queries.rs, so it can be hidden from other viewers
     pub email: String,
+    #[graphql(skip)]
     pub sex: Sex,
     pub year: i32,
+    #[graphql(skip)]
     pub hostel: String,
+    #[graphql(skip)]
     pub mac_address: String,
+    #[graphql(skip)]
     pub discord_id: String,
     pub group_id: i32,
+    pub status: MemberStatus,
+    pub status_since: NaiveDate,
     #[graphql(skip)] // Don't expose internal fields/meta-data
+    #[serde(skip)]
     pub created_at: NaiveDateTime,
 }
 
@@ -39,3 +60,25 @@ pub struct CreateMemberInput {
     pub discord_id: String,
     pub group_id: i32,
 }
+
+#[derive(SimpleObject, FromRow)]
+pub struct MemberStatusChange {
+    pub change_id: i32,
+    pub member_id: i32,
+    pub status: MemberStatus,
+    pub effective_from: NaiveDate,
+    pub reason: Option<String>,
+    /// False while the change is scheduled for a future date.
+    pub applied: bool,
+    #[graphql(skip)]
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(InputObject)]
+pub struct SetMemberStatusInput {
+    pub member_id: i32,
+    pub status: MemberStatus,
+    /// Defaults to today. Changes with a future date are applied by the daily task.
+    pub effective_from: Option<NaiveDate>,
+    pub reason: Option<String>,
+}
@@ -0,0 +1,23 @@
+use async_graphql::SimpleObject;
+
+use super::member::Member;
+
+/// A problem with one row of an import file.
+#[derive(SimpleObject)]
+pub struct ImportRowError {
+    /// Row number as shown in a spreadsheet, the header being row 1.
+    pub row: i32,
+    /// Column the error is about, if any.
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(SimpleObject)]
+pub struct MemberImportReport {
+    pub dry_run: bool,
+    /// Rows that were imported, or would be for a dry run. Zero if there are any errors.
+    pub imported: i32,
+    pub errors: Vec<ImportRowError>,
+    /// The created members. Empty for dry runs and failed imports.
+    pub members: Vec<Member>,
+}
@@ -1,4 +1,16 @@
 pub mod attendance;
+pub mod contribution;
+pub mod digest;
+pub mod discord;
+pub mod group;
+pub mod leaderboard;
 pub mod member;
+pub mod member_import;
+pub mod monthly_report;
+pub mod profile;
 pub mod project;
+pub mod project_activity;
+pub mod rollover;
+pub mod status_update;
 pub mod status_update_streak;
+pub mod webhook;
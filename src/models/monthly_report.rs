@@ -0,0 +1,51 @@
+use async_graphql::SimpleObject;
+use chrono::{NaiveDateTime, NaiveTime};
+use sqlx::FromRow;
+
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct MonthlyReport {
+    pub report_id: i32,
+    pub year: i32,
+    pub month: i32,
+    /// Working days in the whole month.
+    pub working_days: i32,
+    /// Arriving after this time counted as late when the report was generated.
+    pub late_after: NaiveTime,
+    pub generated_at: NaiveDateTime,
+}
+
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct MemberMonthlyReport {
+    pub member_id: i32,
+    /// The member's group when the report was generated.
+    pub group_id: i32,
+    /// Working days on which the member was expected in the lab. Fewer than the month's
+    /// working days if they joined, or stopped being active, during the month.
+    pub working_days: i32,
+    pub days_present: i32,
+    /// Share of `working_days` the member was present on, from 0 to 100.
+    pub attendance_percentage: f64,
+    /// Average hours spent in the lab on days present.
+    pub average_hours: Option<f64>,
+    pub late_arrivals: i32,
+    /// Streaks as they were when the report was generated.
+    pub current_streak: i32,
+    pub max_streak: i32,
+    pub missed_streak: i32,
+}
+
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct GroupMonthlyReport {
+    pub group_id: i32,
+    pub members: i32,
+    pub days_present: i32,
+    /// Average of the members' attendance percentages.
+    pub attendance_percentage: f64,
+    pub average_hours: Option<f64>,
+    pub late_arrivals: i32,
+    /// Members with a current streak when the report was generated.
+    pub members_on_streak: i32,
+}
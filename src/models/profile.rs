@@ -0,0 +1,60 @@
+use async_graphql::{InputObject, MaybeUndefined, SimpleObject};
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(SimpleObject, FromRow)]
+pub struct MemberProfile {
+    pub member_id: i32,
+    pub bio: Option<String>,
+    pub pronouns: Option<String>,
+    pub avatar_url: Option<String>,
+    pub website_url: Option<String>,
+    pub github_handle: Option<String>,
+    pub gitlab_handle: Option<String>,
+    pub linkedin_handle: Option<String>,
+    pub skills: Vec<String>,
+    pub interests: Vec<String>,
+    /// Hidden profiles can't be looked up with `profile(rollNo)`.
+    pub is_public: bool,
+    /// Whether the website and forge/LinkedIn handles show up on the public profile.
+    pub show_socials: bool,
+    pub show_email: bool,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields left out are not changed, `null` clears them.
+#[derive(InputObject, Default)]
+pub struct UpdateProfileInput {
+    pub bio: MaybeUndefined<String>,
+    pub pronouns: MaybeUndefined<String>,
+    #[graphql(validator(url))]
+    pub avatar_url: MaybeUndefined<String>,
+    #[graphql(validator(url))]
+    pub website_url: MaybeUndefined<String>,
+    pub github_handle: MaybeUndefined<String>,
+    pub gitlab_handle: MaybeUndefined<String>,
+    pub linkedin_handle: MaybeUndefined<String>,
+    pub skills: Option<Vec<String>>,
+    pub interests: Option<Vec<String>>,
+    pub is_public: Option<bool>,
+    pub show_socials: Option<bool>,
+    pub show_email: Option<bool>,
+}
+
+/// What anyone can see of a member, following their visibility settings.
+#[derive(SimpleObject, Default)]
+pub struct PublicProfile {
+    pub roll_no: String,
+    pub name: String,
+    pub year: i32,
+    pub bio: Option<String>,
+    pub pronouns: Option<String>,
+    pub avatar_url: Option<String>,
+    pub website_url: Option<String>,
+    pub github_handle: Option<String>,
+    pub gitlab_handle: Option<String>,
+    pub linkedin_handle: Option<String>,
+    pub skills: Vec<String>,
+    pub interests: Vec<String>,
+    pub email: Option<String>,
+}
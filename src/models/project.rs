@@ -1,15 +1,82 @@
-use async_graphql::{InputObject, SimpleObject};
+use async_graphql::{Enum, InputObject, MaybeUndefined, SimpleObject};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
 use sqlx::FromRow;
 
-#[derive(FromRow, SimpleObject)]
+#[derive(Enum, Copy, Clone, Eq, PartialEq, sqlx::Type, Serialize)]
+#[sqlx(type_name = "project_status", rename_all = "lowercase")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProjectStatus {
+    Idea,
+    Active,
+    Paused,
+    Completed,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "project_role", rename_all = "lowercase")]
+pub enum ProjectRole {
+    Lead,
+    Contributor,
+    Mentor,
+}
+
+#[derive(FromRow, SimpleObject, Serialize)]
+#[graphql(complex)]
+#[serde(rename_all = "camelCase")]
 pub struct Project {
     pub project_id: i32,
+    /// The member who created the project, who is also added as its lead.
     pub member_id: i32,
     pub title: Option<String>,
+    pub description: Option<String>,
+    pub repository_url: Option<String>,
+    pub status: ProjectStatus,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub tags: Vec<String>,
 }
 
 #[derive(InputObject)]
 pub struct SetProjectInput {
     pub member_id: i32,
     pub title: String,
+    pub description: Option<String>,
+    #[graphql(validator(url))]
+    pub repository_url: Option<String>,
+    pub status: Option<ProjectStatus>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Fields left out are not changed, `null` clears them.
+#[derive(InputObject)]
+pub struct UpdateProjectInput {
+    pub project_id: i32,
+    pub title: Option<String>,
+    pub description: MaybeUndefined<String>,
+    #[graphql(validator(url))]
+    pub repository_url: MaybeUndefined<String>,
+    pub status: Option<ProjectStatus>,
+    pub start_date: MaybeUndefined<NaiveDate>,
+    pub end_date: MaybeUndefined<NaiveDate>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(FromRow, SimpleObject)]
+#[graphql(complex)]
+pub struct ProjectMember {
+    pub project_id: i32,
+    pub member_id: i32,
+    pub role: ProjectRole,
+    pub joined_at: NaiveDateTime,
+}
+
+/// Adds a member to a project, or changes their role if they're already on it.
+#[derive(InputObject)]
+pub struct ProjectMemberInput {
+    pub project_id: i32,
+    pub member_id: i32,
+    pub role: Option<ProjectRole>,
 }
@@ -0,0 +1,17 @@
+use async_graphql::SimpleObject;
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::FromRow;
+
+/// Activity on a project's repository during a single day.
+#[derive(SimpleObject, FromRow)]
+pub struct ProjectActivity {
+    pub project_id: i32,
+    pub date: NaiveDate,
+    pub commits: i32,
+    /// Pull requests opened on this day.
+    pub pull_requests: i32,
+    /// Issues opened on this day.
+    pub issues: i32,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub imported_at: NaiveDateTime,
+}
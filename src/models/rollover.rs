@@ -0,0 +1,40 @@
+use async_graphql::SimpleObject;
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct YearRollover {
+    pub rollover_id: i32,
+    /// The year the new academic year starts in, e.g. 2026 for 2026-27.
+    pub academic_year: i32,
+    /// False if an admin triggered the rollover.
+    pub scheduled: bool,
+    pub promoted: i32,
+    pub graduated: i32,
+    pub performed_at: NaiveDateTime,
+    pub undone_at: Option<NaiveDateTime>,
+}
+
+/// What a rollover does, or did, to a single member.
+#[derive(SimpleObject, FromRow, Clone)]
+pub struct RolloverChange {
+    pub member_id: i32,
+    pub roll_no: String,
+    pub name: String,
+    pub previous_year: i32,
+    pub new_year: i32,
+    /// Final years don't move up, they become alumni instead.
+    pub graduated: bool,
+}
+
+#[derive(SimpleObject)]
+pub struct RolloverOutcome {
+    pub academic_year: i32,
+    pub dry_run: bool,
+    pub promoted: i32,
+    pub graduated: i32,
+    pub changes: Vec<RolloverChange>,
+    /// The recorded rollover. Missing for dry runs.
+    pub rollover: Option<YearRollover>,
+}
@@ -0,0 +1,28 @@
+use async_graphql::{InputObject, SimpleObject};
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::FromRow;
+
+#[derive(SimpleObject, FromRow)]
+pub struct StatusUpdate {
+    pub update_id: i32,
+    pub member_id: i32,
+    pub date: NaiveDate,
+    pub source_message_id: Option<String>,
+    pub content: String,
+    pub posted_at: NaiveDateTime,
+    #[graphql(skip)] // Don't expose internal fields/meta-data
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(InputObject)]
+pub struct RecordStatusUpdateInput {
+    pub member_id: i32,
+    /// The day this update is for, which may differ from the day it was posted.
+    pub date: NaiveDate,
+    /// ID of the message the update was posted in, e.g. a mail or Discord message ID.
+    pub source_message_id: Option<String>,
+    pub content: String,
+    /// When the update was posted, now by default. Only admin tools can set it, e.g. when
+    /// importing messages after the fact.
+    pub posted_at: Option<NaiveDateTime>,
+}
@@ -1,4 +1,5 @@
-use async_graphql::{InputObject, SimpleObject};
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::{NaiveDate, NaiveDateTime};
 use sqlx::FromRow;
 
 #[derive(SimpleObject, FromRow)]
@@ -6,15 +7,47 @@ pub struct StatusUpdateStreak {
     pub member_id: i32,
     pub current_streak: i32,
     pub max_streak: i32,
+    /// Consecutive working days without an update, 0 while a streak is alive.
+    pub missed_streak: i32,
 }
 
 #[derive(SimpleObject, FromRow)]
 pub struct StatusUpdateStreakInfo {
     pub current_streak: i32,
     pub max_streak: i32,
+    pub missed_streak: i32,
 }
 
 #[derive(InputObject)]
 pub struct StreakInput {
     pub member_id: i32,
 }
+
+/// An unbroken run of working days covered by status updates.
+#[derive(SimpleObject, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StreakRun {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub length: i32,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DayStatus {
+    /// An update was posted before the deadline.
+    Updated,
+    /// An update was posted, but after the deadline.
+    Late,
+    /// No update was posted and the deadline has passed.
+    Missed,
+    /// No update is expected on this day.
+    Excluded,
+    /// No update yet, but the deadline hasn't passed.
+    Pending,
+}
+
+#[derive(SimpleObject)]
+pub struct StatusUpdateDay {
+    pub date: NaiveDate,
+    pub status: DayStatus,
+    pub posted_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,111 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, sqlx::Type)]
+#[sqlx(type_name = "webhook_event_type")]
+pub enum WebhookEventType {
+    /// Only sent by `pingWebhookSubscription`, to check a receiver.
+    #[sqlx(rename = "ping")]
+    Ping,
+    #[sqlx(rename = "member.created")]
+    MemberCreated,
+    /// A member's year or status changed.
+    #[sqlx(rename = "member.updated")]
+    MemberUpdated,
+    #[sqlx(rename = "attendance.marked")]
+    AttendanceMarked,
+    /// A member's current status update streak went back to zero.
+    #[sqlx(rename = "streak.broken")]
+    StreakBroken,
+    #[sqlx(rename = "project.updated")]
+    ProjectUpdated,
+    #[sqlx(rename = "project.deleted")]
+    ProjectDeleted,
+}
+
+impl WebhookEventType {
+    /// The name sent in the `X-Root-Event` header and payloads.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::Ping => "ping",
+            WebhookEventType::MemberCreated => "member.created",
+            WebhookEventType::MemberUpdated => "member.updated",
+            WebhookEventType::AttendanceMarked => "attendance.marked",
+            WebhookEventType::StreakBroken => "streak.broken",
+            WebhookEventType::ProjectUpdated => "project.updated",
+            WebhookEventType::ProjectDeleted => "project.deleted",
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, sqlx::Type)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Not delivered yet, but will be tried again.
+    Pending,
+    Delivered,
+    /// Gave up after too many failed attempts.
+    Failed,
+}
+
+/// A service that receives events from Root.
+#[derive(SimpleObject, FromRow)]
+pub struct WebhookSubscription {
+    pub subscription_id: i32,
+    pub url: String,
+    #[graphql(skip)]
+    pub secret: String,
+    /// Events sent to this subscription. Empty if it receives every event.
+    pub events: Vec<WebhookEventType>,
+    pub active: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(InputObject)]
+pub struct CreateWebhookSubscriptionInput {
+    #[graphql(validator(url))]
+    pub url: String,
+    /// Shared with the receiver and used to sign payloads.
+    #[graphql(validator(min_length = 16))]
+    pub secret: String,
+    /// Leave empty to receive every event.
+    #[graphql(default)]
+    pub events: Vec<WebhookEventType>,
+}
+
+#[derive(SimpleObject, FromRow)]
+pub struct WebhookEvent {
+    pub event_id: i32,
+    pub event_type: WebhookEventType,
+    /// The `data` sent with the event.
+    pub payload: Value,
+    pub created_at: NaiveDateTime,
+}
+
+/// An event being sent to one subscription.
+#[derive(SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct WebhookDelivery {
+    pub delivery_id: i32,
+    pub event_id: i32,
+    pub subscription_id: i32,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    /// When the delivery is tried next, while it is pending.
+    pub next_attempt_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+/// A request sent for a delivery, kept as its log.
+#[derive(SimpleObject, FromRow)]
+pub struct WebhookDeliveryAttempt {
+    pub attempt_id: i32,
+    pub delivery_id: i32,
+    pub attempted_at: NaiveDateTime,
+    /// The receiver's response status, if it responded.
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{header, Client};
+use serde_json::json;
+
+use super::NotifyError;
+
+/// An unresponsive Discord mustn't hold up the daily task.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts messages to Discord channels through their webhooks.
+#[async_trait]
+pub trait DiscordClient: Send + Sync {
+    async fn post_message(&self, webhook_url: &str, content: &str) -> Result<(), NotifyError>;
+}
+
+/// Executes Discord webhooks over HTTP. Webhook URLs can point at any server accepting
+/// the same requests, such as a local stand-in while developing.
+pub struct HttpDiscordClient {
+    http: Client,
+}
+
+impl Default for HttpDiscordClient {
+    fn default() -> Self {
+        Self {
+            http: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("HTTP client must be built properly."),
+        }
+    }
+}
+
+#[async_trait]
+impl DiscordClient for HttpDiscordClient {
+    async fn post_message(&self, webhook_url: &str, content: &str) -> Result<(), NotifyError> {
+        self.http
+            .post(webhook_url)
+            .header(header::USER_AGENT, "amfoss-root")
+            // Only members can be mentioned, never @everyone or roles.
+            .json(&json!({
+                "content": content,
+                "allowed_mentions": { "parse": ["users"] },
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,555 @@
+use std::{fmt, sync::Arc};
+
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use handlebars::Handlebars;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tracing::{error, info};
+
+use crate::{config::timezone, models::discord::NotificationKind, streaks::StreakRules};
+
+pub mod discord;
+
+use discord::DiscordClient;
+
+/// Discord rejects longer messages, so longer notifications are split.
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+const DEFAULT_TEMPLATES: [(NotificationKind, &str); 4] = [
+    (
+        NotificationKind::DailyAbsentees,
+        "**Absent on {{date}}** ({{count}})\n{{#each members}}- {{name}}\n{{/each}}",
+    ),
+    (
+        NotificationKind::LateCheckIns,
+        "**Checked in after {{lateAfter}} on {{date}}**\n{{#each members}}- {{name}} at {{timeIn}}\n{{/each}}",
+    ),
+    (
+        NotificationKind::StreakMilestones,
+        "**Streak milestones**\n{{#each members}}- {{name}} has posted updates for {{streak}} days in a row\n{{/each}}",
+    ),
+    (
+        NotificationKind::StreaksBroken,
+        "**Streaks broken on {{date}}**\n{{#each members}}- {{name}}, who had a best streak of {{maxStreak}}\n{{/each}}",
+    ),
+];
+
+#[derive(Debug)]
+pub enum NotifyError {
+    Database(sqlx::Error),
+    Template(String),
+    Http(reqwest::Error),
+    /// Streaks are only kept as they are now, so streak notifications can't be about other days.
+    StreakDate(NaiveDate),
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Database(e) => write!(f, "Database error: {}", e),
+            NotifyError::Template(e) => write!(f, "Invalid template: {}", e),
+            NotifyError::Http(e) => write!(f, "Posting to Discord failed: {}", e),
+            NotifyError::StreakDate(date) => write!(
+                f,
+                "Streaks are only known as they are now, so this notification must be about {}",
+                date
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NotifyError {}
+
+impl From<sqlx::Error> for NotifyError {
+    fn from(e: sqlx::Error) -> Self {
+        NotifyError::Database(e)
+    }
+}
+
+impl From<reqwest::Error> for NotifyError {
+    fn from(e: reqwest::Error) -> Self {
+        NotifyError::Http(e)
+    }
+}
+
+/// Renders notifications from templates and posts them to the Discord channels routed for them.
+#[derive(Clone)]
+pub struct Notifier {
+    client: Arc<dyn DiscordClient>,
+    templates: Arc<Handlebars<'static>>,
+    /// Current streak lengths worth announcing.
+    milestones: Vec<i32>,
+    late_after: NaiveTime,
+    streak_rules: StreakRules,
+}
+
+impl Notifier {
+    pub fn new(
+        client: Arc<dyn DiscordClient>,
+        milestones: Vec<i32>,
+        late_after: NaiveTime,
+        streak_rules: StreakRules,
+    ) -> Self {
+        let mut templates = Handlebars::new();
+        // Messages are Markdown, not HTML.
+        templates.register_escape_fn(handlebars::no_escape);
+        for (kind, template) in DEFAULT_TEMPLATES {
+            templates
+                .register_template_string(template_name(kind), template)
+                .expect("Default templates must be valid");
+        }
+
+        Self {
+            client,
+            templates: Arc::new(templates),
+            milestones,
+            late_after,
+            streak_rules,
+        }
+    }
+
+    /// Checks that a custom template can be rendered.
+    pub fn validate_template(&self, template: &str) -> Result<(), NotifyError> {
+        self.templates
+            .render_template(template, &json!({}))
+            .map(|_| ())
+            .map_err(|e| NotifyError::Template(e.to_string()))
+    }
+
+    /// Renders a notification about `date` with `template`, or the default template if `None`.
+    /// Returns `None` if there's nothing to notify about, e.g. nobody was absent. Streak
+    /// notifications can only be about the day `streak_date` gives.
+    pub async fn render(
+        &self,
+        kind: NotificationKind,
+        date: NaiveDate,
+        template: Option<&str>,
+        pool: &PgPool,
+    ) -> Result<Option<String>, NotifyError> {
+        let Some(context) = self.context(kind, date, now(), pool).await? else {
+            return Ok(None);
+        };
+        self.render_context(kind, template, &context).map(Some)
+    }
+
+    /// Posts a notification about `date` to every channel routed for `kind`.
+    /// Returns the number of messages posted. Streak notifications can only be about the day
+    /// `streak_date` gives.
+    pub async fn notify(
+        &self,
+        kind: NotificationKind,
+        date: NaiveDate,
+        pool: &PgPool,
+    ) -> Result<i32, NotifyError> {
+        self.notify_at(kind, date, now(), pool).await
+    }
+
+    async fn notify_at(
+        &self,
+        kind: NotificationKind,
+        date: NaiveDate,
+        now: NaiveDateTime,
+        pool: &PgPool,
+    ) -> Result<i32, NotifyError> {
+        let routes = sqlx::query_as::<_, (String, Option<String>)>(
+            "SELECT c.webhook_url, r.template FROM DiscordRoute r
+            JOIN DiscordChannel c ON c.channel_id = r.channel_id
+            WHERE r.kind = $1 ORDER BY c.channel_id",
+        )
+        .bind(kind)
+        .fetch_all(pool)
+        .await?;
+        if routes.is_empty() {
+            return Ok(0);
+        }
+
+        let Some(context) = self.context(kind, date, now, pool).await? else {
+            return Ok(0);
+        };
+
+        self.post(kind, &routes, &context).await
+    }
+
+    /// Renders the notification for each `(webhook_url, template)` route and posts it.
+    async fn post(
+        &self,
+        kind: NotificationKind,
+        routes: &[(String, Option<String>)],
+        context: &Value,
+    ) -> Result<i32, NotifyError> {
+        let mut posted = 0;
+        for (webhook_url, template) in routes {
+            let content = self.render_context(kind, template.as_deref(), context)?;
+            for message in split_message(&content) {
+                self.client.post_message(webhook_url, &message).await?;
+                posted += 1;
+            }
+        }
+
+        Ok(posted)
+    }
+
+    /// Sends every notification due after the daily task has updated attendance and streaks.
+    /// Attendance notifications are about yesterday, and nothing is sent after a day on which
+    /// nobody is expected, since streaks can't have changed.
+    pub async fn send_daily_notifications(&self, now: NaiveDateTime, pool: &PgPool) {
+        let yesterday = now.date() - Duration::days(1);
+        if !self.streak_rules.is_working_day(yesterday) {
+            return;
+        }
+
+        let notifications = [
+            NotificationKind::DailyAbsentees,
+            NotificationKind::LateCheckIns,
+            NotificationKind::StreakMilestones,
+            NotificationKind::StreaksBroken,
+        ];
+        for kind in notifications {
+            let date = self.streak_date(kind, now).unwrap_or(yesterday);
+            match self.notify_at(kind, date, now, pool).await {
+                Ok(0) => {}
+                Ok(posted) => info!("Posted {} Discord messages for {:?}", posted, kind),
+                Err(e) => error!("Failed to send {:?} notification: {}", kind, e),
+            }
+        }
+    }
+
+    /// The day a streak notification is about at `now`: today for milestones, and the last
+    /// day an update was due for broken streaks. `None` for other kinds.
+    pub fn streak_date(&self, kind: NotificationKind, now: NaiveDateTime) -> Option<NaiveDate> {
+        match kind {
+            NotificationKind::StreakMilestones => Some(now.date()),
+            NotificationKind::StreaksBroken => Some(self.streak_rules.last_due_day(now)),
+            NotificationKind::DailyAbsentees | NotificationKind::LateCheckIns => None,
+        }
+    }
+
+    fn render_context(
+        &self,
+        kind: NotificationKind,
+        template: Option<&str>,
+        context: &Value,
+    ) -> Result<String, NotifyError> {
+        match template {
+            Some(template) => self.templates.render_template(template, context),
+            None => self.templates.render(template_name(kind), context),
+        }
+        .map_err(|e| NotifyError::Template(e.to_string()))
+    }
+
+    /// The values templates can use. Every notification has `date`, `count` and `members`,
+    /// a list of members with their `name`, `rollNo` and `discordId`.
+    async fn context(
+        &self,
+        kind: NotificationKind,
+        date: NaiveDate,
+        now: NaiveDateTime,
+        pool: &PgPool,
+    ) -> Result<Option<Value>, NotifyError> {
+        if let Some(streak_date) = self.streak_date(kind, now) {
+            if date != streak_date {
+                return Err(NotifyError::StreakDate(streak_date));
+            }
+        }
+
+        let mut context = json!({ "date": date.to_string() });
+
+        let members: Vec<Value> = match kind {
+            NotificationKind::DailyAbsentees => sqlx::query_as::<_, (String, String, String)>(
+                "SELECT m.name, m.roll_no, m.discord_id FROM Attendance a
+                JOIN Member m ON m.member_id = a.member_id
+                WHERE a.date = $1 AND NOT a.is_present AND m.status = 'active'
+                ORDER BY m.name",
+            )
+            .bind(date)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|(name, roll_no, discord_id)| {
+                json!({ "name": name, "rollNo": roll_no, "discordId": discord_id })
+            })
+            .collect(),
+            NotificationKind::LateCheckIns => {
+                context["lateAfter"] = json!(self.late_after.format("%H:%M").to_string());
+                sqlx::query_as::<_, (String, String, String, NaiveTime)>(
+                    "SELECT m.name, m.roll_no, m.discord_id, a.time_in FROM Attendance a
+                    JOIN Member m ON m.member_id = a.member_id
+                    WHERE a.date = $1 AND a.is_present AND a.time_in > $2 AND m.status = 'active'
+                    ORDER BY a.time_in",
+                )
+                .bind(date)
+                .bind(self.late_after)
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .map(|(name, roll_no, discord_id, time_in)| {
+                    json!({
+                        "name": name,
+                        "rollNo": roll_no,
+                        "discordId": discord_id,
+                        "timeIn": time_in.format("%H:%M").to_string(),
+                    })
+                })
+                .collect()
+            }
+            NotificationKind::StreakMilestones => sqlx::query_as::<_, (String, String, String, i32)>(
+                "SELECT m.name, m.roll_no, m.discord_id, s.current_streak FROM StatusUpdateStreak s
+                JOIN Member m ON m.member_id = s.member_id
+                WHERE s.current_streak = ANY($1) AND m.status = 'active'
+                ORDER BY s.current_streak DESC, m.name",
+            )
+            .bind(&self.milestones)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|(name, roll_no, discord_id, streak)| {
+                json!({ "name": name, "rollNo": roll_no, "discordId": discord_id, "streak": streak })
+            })
+            .collect(),
+            // A missed streak of one means the last due day was missed right after an update.
+            NotificationKind::StreaksBroken => sqlx::query_as::<_, (String, String, String, i32)>(
+                "SELECT m.name, m.roll_no, m.discord_id, s.max_streak FROM StatusUpdateStreak s
+                JOIN Member m ON m.member_id = s.member_id
+                WHERE s.missed_streak = 1 AND s.max_streak > 0 AND m.status = 'active'
+                ORDER BY m.name",
+            )
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|(name, roll_no, discord_id, max_streak)| {
+                json!({
+                    "name": name,
+                    "rollNo": roll_no,
+                    "discordId": discord_id,
+                    "maxStreak": max_streak,
+                })
+            })
+            .collect(),
+        };
+
+        if members.is_empty() {
+            return Ok(None);
+        }
+        context["count"] = json!(members.len());
+        context["members"] = json!(members);
+        Ok(Some(context))
+    }
+}
+
+fn now() -> NaiveDateTime {
+    Local::now().with_timezone(&timezone()).naive_local()
+}
+
+fn template_name(kind: NotificationKind) -> &'static str {
+    match kind {
+        NotificationKind::DailyAbsentees => "daily_absentees",
+        NotificationKind::LateCheckIns => "late_check_ins",
+        NotificationKind::StreakMilestones => "streak_milestones",
+        NotificationKind::StreaksBroken => "streaks_broken",
+    }
+}
+
+/// Splits `content` into messages Discord accepts, between lines where possible.
+fn split_message(content: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current = String::new();
+
+    for line in content.trim_end().lines() {
+        let mut line = line;
+        // A single line that is too long has to be cut.
+        while line.chars().count() > MAX_MESSAGE_LENGTH {
+            let cut = line
+                .char_indices()
+                .nth(MAX_MESSAGE_LENGTH)
+                .map(|(index, _)| index)
+                .unwrap_or(line.len());
+            if !current.is_empty() {
+                messages.push(std::mem::take(&mut current));
+            }
+            messages.push(line[..cut].to_string());
+            line = &line[cut..];
+        }
+
+        if !current.is_empty()
+            && current.chars().count() + line.chars().count() + 1 > MAX_MESSAGE_LENGTH
+        {
+            messages.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+
+    if !current.trim().is_empty() {
+        messages.push(current);
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use chrono::Weekday;
+
+    use super::*;
+
+    /// Records messages instead of posting them.
+    #[derive(Default)]
+    struct RecordingClient {
+        posted: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl DiscordClient for RecordingClient {
+        async fn post_message(&self, webhook_url: &str, content: &str) -> Result<(), NotifyError> {
+            self.posted
+                .lock()
+                .unwrap()
+                .push((webhook_url.to_string(), content.to_string()));
+            Ok(())
+        }
+    }
+
+    fn notifier(client: Arc<RecordingClient>) -> Notifier {
+        let streak_rules = StreakRules::new(NaiveTime::MIN, vec![Weekday::Sun]).unwrap();
+        Notifier::new(
+            client,
+            vec![7, 30],
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+            streak_rules,
+        )
+    }
+
+    fn absentees(count: usize) -> Value {
+        let members: Vec<Value> = (0..count)
+            .map(|i| json!({ "name": format!("Member {}", i), "rollNo": "", "discordId": "" }))
+            .collect();
+        json!({ "date": "2025-01-15", "count": count, "members": members })
+    }
+
+    #[tokio::test]
+    async fn posts_rendered_notification_to_every_route() {
+        let client = Arc::new(RecordingClient::default());
+        let routes = [
+            ("https://discord.test/a".to_string(), None),
+            (
+                "https://discord.test/b".to_string(),
+                Some("{{count}} absent".to_string()),
+            ),
+        ];
+
+        let posted = notifier(client.clone())
+            .post(NotificationKind::DailyAbsentees, &routes, &absentees(2))
+            .await
+            .unwrap();
+
+        assert_eq!(posted, 2);
+        assert_eq!(
+            *client.posted.lock().unwrap(),
+            [
+                (
+                    "https://discord.test/a".to_string(),
+                    "**Absent on 2025-01-15** (2)\n- Member 0\n- Member 1".to_string()
+                ),
+                ("https://discord.test/b".to_string(), "2 absent".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn splits_long_notifications() {
+        let client = Arc::new(RecordingClient::default());
+        let routes = [("https://discord.test/a".to_string(), None)];
+
+        let posted = notifier(client.clone())
+            .post(NotificationKind::DailyAbsentees, &routes, &absentees(300))
+            .await
+            .unwrap();
+
+        let posted_messages = client.posted.lock().unwrap();
+        assert_eq!(posted as usize, posted_messages.len());
+        assert!(posted > 1);
+        assert!(posted_messages
+            .iter()
+            .all(|(_, message)| message.chars().count() <= MAX_MESSAGE_LENGTH));
+        let lines: Vec<&str> = posted_messages
+            .iter()
+            .flat_map(|(_, message)| message.lines())
+            .collect();
+        assert_eq!(lines.len(), 301);
+        assert_eq!(lines[300], "- Member 299");
+    }
+
+    #[test]
+    fn split_message_keeps_short_content_whole() {
+        assert_eq!(split_message("a\nb\n"), ["a\nb"]);
+        assert!(split_message("\n").is_empty());
+    }
+
+    #[test]
+    fn split_message_breaks_between_lines() {
+        let line = "x".repeat(1500);
+        let content = format!("{}\n{}\n{}", line, line, "y");
+        assert_eq!(
+            split_message(&content),
+            [line.clone(), format!("{}\ny", line)]
+        );
+    }
+
+    #[test]
+    fn split_message_cuts_overlong_lines() {
+        let content = format!("short\n{}", "é".repeat(MAX_MESSAGE_LENGTH + 10));
+        let messages = split_message(&content);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], "short");
+        assert_eq!(messages[1].chars().count(), MAX_MESSAGE_LENGTH);
+        assert_eq!(messages[2], "é".repeat(10));
+    }
+
+    #[test]
+    fn streak_notifications_are_about_current_streaks() {
+        let notifier = notifier(Arc::new(RecordingClient::default()));
+        // A Monday, after the deadline for Saturday's update. Sunday isn't a working day.
+        let now = NaiveDate::from_ymd_opt(2025, 1, 13)
+            .unwrap()
+            .and_hms_opt(0, 30, 0)
+            .unwrap();
+
+        assert_eq!(
+            notifier.streak_date(NotificationKind::StreakMilestones, now),
+            NaiveDate::from_ymd_opt(2025, 1, 13)
+        );
+        assert_eq!(
+            notifier.streak_date(NotificationKind::StreaksBroken, now),
+            NaiveDate::from_ymd_opt(2025, 1, 11)
+        );
+        assert_eq!(
+            notifier.streak_date(NotificationKind::DailyAbsentees, now),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_streak_notifications_about_other_days() {
+        let notifier = notifier(Arc::new(RecordingClient::default()));
+        // Never connects, the date is rejected before anything is queried.
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let now = NaiveDate::from_ymd_opt(2025, 1, 13)
+            .unwrap()
+            .and_hms_opt(0, 30, 0)
+            .unwrap();
+
+        let result = notifier
+            .context(
+                NotificationKind::StreakMilestones,
+                NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                now,
+                &pool,
+            )
+            .await;
+        assert!(matches!(result, Err(NotifyError::StreakDate(date)) if date == now.date()));
+    }
+}
@@ -0,0 +1,411 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use reqwest::{header, Client, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::warn;
+
+use super::{DailyActivity, ForgeClient, ForgeError, Repository};
+use crate::config::timezone;
+
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+const PER_PAGE: usize = 100;
+/// Caps the number of requests spent counting commits of a single, very busy day.
+const MAX_COMMIT_PAGES: u32 = 10;
+/// The Search API's limit resets every minute, so waiting that long once is worth it.
+/// Longer waits, e.g. for the hourly core limit, fail the import instead.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Talks to the GitHub REST API. `api_url` can point at any server implementing
+/// the same endpoints, such as a local stand-in while developing.
+pub struct GitHubClient {
+    http: Client,
+    api_url: String,
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    total_count: i32,
+}
+
+/// The first and last second of `date` in the configured timezone, in UTC as GitHub expects.
+fn utc_bounds(date: NaiveDate) -> (String, String) {
+    let start = start_of_day(date);
+    let end = start_of_day(date + chrono::Duration::days(1)) - chrono::Duration::seconds(1);
+    let format = |time: DateTime<Utc>| time.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    (format(start), format(end))
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    timezone()
+        .from_local_datetime(&midnight)
+        .earliest()
+        // Midnight can fall into a daylight saving gap, moving the start of the day.
+        .or_else(|| {
+            timezone()
+                .from_local_datetime(&(midnight + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .expect("Days must start within their first hour")
+        .with_timezone(&Utc)
+}
+
+/// How long to wait before trying again if GitHub rate limited the request.
+fn rate_limit_wait(response: &Response) -> Option<Duration> {
+    let headers = response.headers();
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+
+    let retry_after = header(header::RETRY_AFTER.as_str());
+    let limited = match response.status() {
+        StatusCode::TOO_MANY_REQUESTS => true,
+        // Secondary rate limits come with `Retry-After`, primary ones with no requests remaining.
+        StatusCode::FORBIDDEN => {
+            retry_after.is_some() || header("x-ratelimit-remaining") == Some(0)
+        }
+        _ => false,
+    };
+    if !limited {
+        return None;
+    }
+
+    let wait = match (retry_after, header("x-ratelimit-reset")) {
+        (Some(seconds), _) => seconds,
+        (None, Some(reset)) => (reset as i64 - Utc::now().timestamp()).max(0) as u64 + 1,
+        // GitHub asks to wait at least a minute when it doesn't say how long.
+        (None, None) => 60,
+    };
+    Some(Duration::from_secs(wait))
+}
+
+impl GitHubClient {
+    pub fn new(api_url: String, token: Option<String>) -> Self {
+        Self {
+            http: Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        let request = self
+            .http
+            .get(format!("{}{}", self.api_url, path))
+            .header(header::USER_AGENT, "amfoss-root")
+            .header(header::ACCEPT, "application/vnd.github+json");
+
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Sends `request`, waiting once for the rate limit to reset if it's short enough.
+    async fn fetch<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ForgeError> {
+        let mut waited = false;
+        loop {
+            let response = request
+                .try_clone()
+                .expect("GET requests must be clonable")
+                .send()
+                .await?;
+
+            match rate_limit_wait(&response) {
+                Some(wait) if !waited && wait <= MAX_RATE_LIMIT_WAIT => {
+                    warn!(
+                        "Rate limited by GitHub, trying again in {}s",
+                        wait.as_secs()
+                    );
+                    tokio::time::sleep(wait).await;
+                    waited = true;
+                }
+                Some(wait) => return Err(ForgeError::RateLimited(wait)),
+                None => return Ok(response.error_for_status()?.json().await?),
+            }
+        }
+    }
+
+    async fn count_commits(
+        &self,
+        repository: &Repository,
+        date: NaiveDate,
+    ) -> Result<i32, ForgeError> {
+        let path = format!("/repos/{}/{}/commits", repository.owner, repository.name);
+        let (since, until) = utc_bounds(date);
+        let mut commits = 0;
+
+        for page in 1..=MAX_COMMIT_PAGES {
+            let batch: Vec<serde_json::Value> = self
+                .fetch(self.get(&path).query(&[
+                    ("since", since.as_str()),
+                    ("until", until.as_str()),
+                    ("per_page", &PER_PAGE.to_string()),
+                    ("page", &page.to_string()),
+                ]))
+                .await?;
+
+            commits += batch.len() as i32;
+            if batch.len() < PER_PAGE {
+                return Ok(commits);
+            }
+        }
+
+        warn!(
+            "{}/{} has more than {} commits on {}, only counting those",
+            repository.owner, repository.name, commits, date
+        );
+        Ok(commits)
+    }
+
+    /// Counts issues or pull requests, depending on `kind`, opened on `date`.
+    async fn count_opened(
+        &self,
+        repository: &Repository,
+        kind: &str,
+        date: NaiveDate,
+    ) -> Result<i32, ForgeError> {
+        let (start, end) = utc_bounds(date);
+        let query = format!(
+            "repo:{}/{} type:{} created:{}..{}",
+            repository.owner, repository.name, kind, start, end
+        );
+        let result: SearchResult = self
+            .fetch(
+                self.get("/search/issues")
+                    .query(&[("q", query.as_str()), ("per_page", "1")]),
+            )
+            .await?;
+
+        Ok(result.total_count)
+    }
+}
+
+#[async_trait]
+impl ForgeClient for GitHubClient {
+    fn supports(&self, host: &str) -> bool {
+        host == "github.com" || host == "www.github.com"
+    }
+
+    async fn daily_activity(
+        &self,
+        repository: &Repository,
+        date: NaiveDate,
+    ) -> Result<DailyActivity, ForgeError> {
+        Ok(DailyActivity {
+            commits: self.count_commits(repository, date).await?,
+            pull_requests: self.count_opened(repository, "pr", date).await?,
+            issues: self.count_opened(repository, "issue", date).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, VecDeque},
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{
+        extract::{Query, State},
+        http::HeaderMap,
+        response::{IntoResponse, Response},
+        routing::get,
+        Json, Router,
+    };
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// A request the fake received: which endpoint, its headers and query parameters.
+    type Request = (&'static str, HeaderMap, HashMap<String, String>);
+
+    /// A local stand-in for the GitHub API with `commits` commits on every day. Searches
+    /// are answered with `limits` in turn before succeeding.
+    #[derive(Clone)]
+    struct FakeGitHub {
+        commits: usize,
+        limits: Arc<Mutex<VecDeque<Response<String>>>>,
+        requests: Arc<Mutex<Vec<Request>>>,
+    }
+
+    impl FakeGitHub {
+        async fn start(commits: usize, limits: Vec<Response<String>>) -> (Self, String) {
+            let github = Self {
+                commits,
+                limits: Arc::new(Mutex::new(limits.into())),
+                requests: Arc::default(),
+            };
+            let app = Router::new()
+                .route("/repos/amfoss/root/commits", get(list_commits))
+                .route("/search/issues", get(search_issues))
+                .with_state(github.clone());
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            (github, url)
+        }
+
+        fn requests_to(&self, endpoint: &str) -> Vec<(HeaderMap, HashMap<String, String>)> {
+            let requests = self.requests.lock().unwrap();
+            requests
+                .iter()
+                .filter(|(e, _, _)| *e == endpoint)
+                .map(|(_, headers, params)| (headers.clone(), params.clone()))
+                .collect()
+        }
+    }
+
+    async fn list_commits(
+        State(github): State<FakeGitHub>,
+        headers: HeaderMap,
+        Query(params): Query<HashMap<String, String>>,
+    ) -> Json<Vec<Value>> {
+        let per_page: usize = params["per_page"].parse().unwrap();
+        let page: usize = params["page"].parse().unwrap();
+        github
+            .requests
+            .lock()
+            .unwrap()
+            .push(("commits", headers, params));
+        let remaining = github.commits.saturating_sub((page - 1) * per_page);
+        Json(vec![json!({}); remaining.min(per_page)])
+    }
+
+    async fn search_issues(
+        State(github): State<FakeGitHub>,
+        headers: HeaderMap,
+        Query(params): Query<HashMap<String, String>>,
+    ) -> Response {
+        let total_count = if params["q"].contains("type:pr") {
+            3
+        } else {
+            7
+        };
+        github
+            .requests
+            .lock()
+            .unwrap()
+            .push(("search", headers, params));
+        match github.limits.lock().unwrap().pop_front() {
+            Some(limit) => limit.into_response(),
+            None => Json(json!({ "total_count": total_count })).into_response(),
+        }
+    }
+
+    fn limited(status: StatusCode, headers: &[(&'static str, String)]) -> Response<String> {
+        let mut response = Response::new(String::new());
+        *response.status_mut() = status;
+        for (name, value) in headers {
+            response.headers_mut().insert(*name, value.parse().unwrap());
+        }
+        response
+    }
+
+    fn repository() -> Repository {
+        Repository::from_url("https://github.com/amfoss/root").unwrap()
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap()
+    }
+
+    #[test]
+    fn bounds_cover_the_local_day() {
+        // The default timezone is Asia/Kolkata, 5:30 ahead of UTC.
+        assert_eq!(
+            utc_bounds(date()),
+            (
+                "2026-10-16T18:30:00Z".to_string(),
+                "2026-10-17T18:29:59Z".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_commits_across_pages_and_opened_items() {
+        let (github, url) = FakeGitHub::start(250, vec![]).await;
+        let client = GitHubClient::new(url, Some("token".to_string()));
+
+        let activity = client.daily_activity(&repository(), date()).await.unwrap();
+
+        assert_eq!(activity.commits, 250);
+        assert_eq!(activity.pull_requests, 3);
+        assert_eq!(activity.issues, 7);
+
+        let commits = github.requests_to("commits");
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].1["since"], "2026-10-16T18:30:00Z");
+        assert_eq!(commits[0].1["until"], "2026-10-17T18:29:59Z");
+        for (headers, params) in github.requests_to("search") {
+            assert_eq!(headers[header::AUTHORIZATION], "Bearer token");
+            assert!(params["q"].ends_with("created:2026-10-16T18:30:00Z..2026-10-17T18:29:59Z"));
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_counting_commits_at_the_page_cap() {
+        let (github, url) = FakeGitHub::start(5000, vec![]).await;
+        let client = GitHubClient::new(url, None);
+
+        let activity = client.daily_activity(&repository(), date()).await.unwrap();
+
+        assert_eq!(activity.commits, MAX_COMMIT_PAGES as i32 * PER_PAGE as i32);
+        let requests = github.requests_to("commits");
+        assert_eq!(requests.len(), MAX_COMMIT_PAGES as usize);
+        assert!(!requests[0].0.contains_key(header::AUTHORIZATION));
+    }
+
+    #[tokio::test]
+    async fn waits_out_short_rate_limits() {
+        let limit = limited(
+            StatusCode::TOO_MANY_REQUESTS,
+            &[("retry-after", "0".into())],
+        );
+        let (github, url) = FakeGitHub::start(0, vec![limit]).await;
+        let client = GitHubClient::new(url, None);
+
+        let activity = client.daily_activity(&repository(), date()).await.unwrap();
+
+        assert_eq!(activity.pull_requests, 3);
+        assert_eq!(github.requests_to("search").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_long_rate_limits() {
+        let reset = Utc::now().timestamp() + 3600;
+        let limit = limited(
+            StatusCode::FORBIDDEN,
+            &[
+                ("x-ratelimit-remaining", "0".into()),
+                ("x-ratelimit-reset", reset.to_string()),
+            ],
+        );
+        let (github, url) = FakeGitHub::start(0, vec![limit]).await;
+        let client = GitHubClient::new(url, None);
+
+        let result = client.daily_activity(&repository(), date()).await;
+
+        assert!(matches!(result, Err(ForgeError::RateLimited(wait)) if wait > MAX_RATE_LIMIT_WAIT));
+        assert_eq!(github.requests_to("search").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn fails_on_error_responses() {
+        let (_, url) = FakeGitHub::start(0, vec![]).await;
+        let client = GitHubClient::new(url, None);
+        let elsewhere = Repository::from_url("https://github.com/amfoss/website").unwrap();
+
+        let result = client.daily_activity(&elsewhere, date()).await;
+
+        assert!(matches!(result, Err(ForgeError::Http(_))));
+    }
+}
@@ -0,0 +1,184 @@
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use tracing::{debug, error, warn};
+
+use crate::models::project_activity::ProjectActivity;
+
+pub mod github;
+
+/// A repository on some forge, parsed from a project's repository URL.
+pub struct Repository {
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+}
+
+impl Repository {
+    /// Parses URLs like `https://github.com/amfoss/root` or `https://github.com/amfoss/root.git`.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let path = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))?;
+        let mut parts = path.trim_end_matches('/').split('/');
+
+        let host = parts.next()?.to_lowercase();
+        let owner = parts.next()?.to_string();
+        let name = parts.next()?.trim_end_matches(".git").to_string();
+
+        if owner.is_empty() || name.is_empty() || parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self { host, owner, name })
+    }
+}
+
+/// Counts of things that happened in a repository during a day.
+#[derive(Default)]
+pub struct DailyActivity {
+    pub commits: i32,
+    pub pull_requests: i32,
+    pub issues: i32,
+}
+
+#[derive(Debug)]
+pub enum ForgeError {
+    /// No client is registered for the repository's host.
+    UnsupportedHost(String),
+    InvalidRepositoryUrl(String),
+    /// The forge won't take more requests for at least this long.
+    RateLimited(std::time::Duration),
+    Http(reqwest::Error),
+}
+
+impl fmt::Display for ForgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForgeError::UnsupportedHost(host) => write!(f, "No forge client for host {}", host),
+            ForgeError::InvalidRepositoryUrl(url) => write!(f, "Invalid repository URL: {}", url),
+            ForgeError::RateLimited(wait) => write!(
+                f,
+                "Rate limited by the forge for another {}s",
+                wait.as_secs()
+            ),
+            ForgeError::Http(e) => write!(f, "Forge request failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ForgeError {}
+
+impl From<reqwest::Error> for ForgeError {
+    fn from(e: reqwest::Error) -> Self {
+        ForgeError::Http(e)
+    }
+}
+
+/// Fetches repository activity from a forge such as GitHub.
+#[async_trait]
+pub trait ForgeClient: Send + Sync {
+    /// Whether this client can handle repositories on `host`, e.g. `github.com`.
+    fn supports(&self, host: &str) -> bool;
+
+    async fn daily_activity(
+        &self,
+        repository: &Repository,
+        date: NaiveDate,
+    ) -> Result<DailyActivity, ForgeError>;
+}
+
+/// Picks the first client that supports a repository's host.
+#[derive(Clone, Default)]
+pub struct ForgeClients(Vec<Arc<dyn ForgeClient>>);
+
+impl ForgeClients {
+    pub fn new(clients: Vec<Arc<dyn ForgeClient>>) -> Self {
+        Self(clients)
+    }
+
+    pub async fn daily_activity(
+        &self,
+        repository_url: &str,
+        date: NaiveDate,
+    ) -> Result<DailyActivity, ForgeError> {
+        let repository = Repository::from_url(repository_url)
+            .ok_or_else(|| ForgeError::InvalidRepositoryUrl(repository_url.to_string()))?;
+        let client = self
+            .0
+            .iter()
+            .find(|client| client.supports(&repository.host))
+            .ok_or_else(|| ForgeError::UnsupportedHost(repository.host.clone()))?;
+
+        client.daily_activity(&repository, date).await
+    }
+}
+
+/// Fetches a project's activity for `date` and stores it, replacing any earlier snapshot of that day.
+pub async fn import_project_activity(
+    project_id: i32,
+    repository_url: &str,
+    date: NaiveDate,
+    clients: &ForgeClients,
+    pool: &PgPool,
+) -> Result<ProjectActivity, Box<dyn std::error::Error + Send + Sync>> {
+    let activity = clients.daily_activity(repository_url, date).await?;
+
+    let snapshot = sqlx::query_as::<_, ProjectActivity>(
+        "INSERT INTO ProjectActivity (project_id, date, commits, pull_requests, issues)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (project_id, date) DO UPDATE SET
+            commits = EXCLUDED.commits,
+            pull_requests = EXCLUDED.pull_requests,
+            issues = EXCLUDED.issues,
+            imported_at = CURRENT_TIMESTAMP
+        RETURNING *",
+    )
+    .bind(project_id)
+    .bind(date)
+    .bind(activity.commits)
+    .bind(activity.pull_requests)
+    .bind(activity.issues)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(snapshot)
+}
+
+/// Imports activity for `date` for every project with a repository URL.
+pub async fn import_all_project_activity(date: NaiveDate, clients: &ForgeClients, pool: &PgPool) {
+    let projects = sqlx::query_as::<_, (i32, String)>(
+        "SELECT project_id, repository_url FROM Project WHERE repository_url IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await;
+
+    let projects = match projects {
+        Ok(projects) => projects,
+        Err(e) => {
+            error!("Failed to fetch projects: {:?}", e);
+            return;
+        }
+    };
+
+    for (project_id, repository_url) in projects {
+        match import_project_activity(project_id, &repository_url, date, clients, pool).await {
+            Ok(_) => debug!(
+                "Imported activity on {} for project ID: {}",
+                date, project_id
+            ),
+            // Other projects may still get through. Skipped days can be imported with
+            // `importProjectActivity` later on.
+            Err(e) if matches!(e.downcast_ref(), Some(ForgeError::RateLimited(_))) => warn!(
+                "Skipped importing activity for project ID: {}: {}",
+                project_id, e
+            ),
+            Err(e) => error!(
+                "Failed to import activity for project ID: {}: {}",
+                project_id, e
+            ),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tower::{Layer, Service};
+
+/// Buckets are only pruned once the map grows past this many clients, and at most once
+/// per `PRUNE_INTERVAL`, so requests don't each pay for a scan of every client.
+const PRUNE_THRESHOLD: usize = 10_000;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy)]
+pub struct RateLimitConfig {
+    /// Maximum number of requests a client can make in a burst.
+    pub burst: u32,
+    /// Tokens added back to each bucket per second.
+    pub per_second: f64,
+    /// Use the last address in `X-Forwarded-For`, the one the reverse proxy appended, instead
+    /// of the peer address. Clients can put anything before it. Only enable this behind a
+    /// reverse proxy that sets the header.
+    pub trust_forwarded_for: bool,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+struct Buckets {
+    clients: HashMap<IpAddr, Bucket>,
+    last_pruned: Instant,
+}
+
+/// Per-client token bucket rate limiter for the GraphQL endpoint.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    config: RateLimitConfig,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimitLayer {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::new(Mutex::new(Buckets {
+                clients: HashMap::new(),
+                last_pruned: Instant::now(),
+            })),
+        }
+    }
+
+    /// Takes a token from the client's bucket. Returns the number of seconds
+    /// until the next token is available if the bucket is empty.
+    fn try_acquire(&self, client: IpAddr) -> Result<(), u64> {
+        let now = Instant::now();
+        let burst = self.config.burst as f64;
+        let mut buckets = self.buckets.lock().expect("Rate limiter lock poisoned");
+
+        if buckets.clients.len() > PRUNE_THRESHOLD
+            && now.duration_since(buckets.last_pruned) >= PRUNE_INTERVAL
+        {
+            // Buckets that would have refilled completely carry no state worth keeping.
+            let per_second = self.config.per_second;
+            buckets.clients.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.last_refill).as_secs_f64() * per_second
+                    < burst
+            });
+            buckets.last_pruned = now;
+        }
+
+        let bucket = buckets.clients.entry(client).or_insert(Bucket {
+            tokens: burst,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.config.per_second).min(burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) / self.config.per_second;
+            Err(wait.ceil() as u64)
+        }
+    }
+
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        if self.config.trust_forwarded_for {
+            let forwarded = request
+                .headers()
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .and_then(|ip| ip.trim().parse().ok());
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: RateLimitLayer,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // Clients we can't identify share no bucket and are let through.
+        let verdict = match self.limiter.client_ip(&request) {
+            Some(ip) => self.limiter.try_acquire(ip),
+            None => Ok(()),
+        };
+
+        match verdict {
+            Ok(()) => Box::pin(self.inner.call(request)),
+            Err(retry_after) => Box::pin(async move { Ok(rate_limited(retry_after)) }),
+        }
+    }
+}
+
+/// Shaped like a GraphQL response so clients can handle it the same way as other errors.
+fn rate_limited(retry_after: u64) -> Response {
+    let body = json!({
+        "data": null,
+        "errors": [{
+            "message": "Rate limit exceeded",
+            "extensions": {
+                "code": "RATE_LIMITED",
+                "retryAfter": retry_after,
+            }
+        }]
+    });
+
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [("retry-after", retry_after.to_string())],
+        Json(body),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+
+    use super::*;
+
+    fn limiter(trust_forwarded_for: bool) -> RateLimitLayer {
+        RateLimitLayer::new(RateLimitConfig {
+            burst: 2,
+            per_second: 0.5,
+            trust_forwarded_for,
+        })
+    }
+
+    fn request(forwarded_for: Option<&str>) -> Request {
+        let mut request = Request::new(Body::empty());
+        if let Some(forwarded_for) = forwarded_for {
+            request
+                .headers_mut()
+                .insert("x-forwarded-for", forwarded_for.parse().unwrap());
+        }
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+        request
+    }
+
+    #[test]
+    fn limits_bursts_per_client() {
+        let limiter = limiter(false);
+        let client: IpAddr = "192.0.2.1".parse().unwrap();
+        let other: IpAddr = "192.0.2.2".parse().unwrap();
+
+        assert_eq!(limiter.try_acquire(client), Ok(()));
+        assert_eq!(limiter.try_acquire(client), Ok(()));
+        assert_eq!(limiter.try_acquire(client), Err(2));
+        assert_eq!(limiter.try_acquire(other), Ok(()));
+    }
+
+    #[test]
+    fn uses_peer_address_unless_forwarded_for_is_trusted() {
+        let request = request(Some("203.0.113.9"));
+        assert_eq!(
+            limiter(false).client_ip(&request),
+            Some("10.0.0.1".parse().unwrap())
+        );
+        assert_eq!(
+            limiter(true).client_ip(&request),
+            Some("203.0.113.9".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn uses_address_appended_by_proxy() {
+        // Clients can send their own header, which the proxy appends to.
+        let request = request(Some("198.51.100.7, 203.0.113.9"));
+        assert_eq!(
+            limiter(true).client_ip(&request),
+            Some("203.0.113.9".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn falls_back_to_peer_address_for_invalid_forwarded_for() {
+        let request = request(Some("203.0.113.9, unknown"));
+        assert_eq!(
+            limiter(true).client_ip(&request),
+            Some("10.0.0.1".parse().unwrap())
+        );
+    }
+}
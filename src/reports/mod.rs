@@ -0,0 +1,110 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use sqlx::PgPool;
+
+use crate::{models::monthly_report::MonthlyReport, streaks::StreakRules};
+
+/// Settings for generating monthly reports.
+#[derive(Clone)]
+pub struct ReportRules {
+    /// Arriving in the lab after this time counts as a late arrival.
+    pub late_after: NaiveTime,
+}
+
+/// First day of the month before the one `date` is in.
+pub fn previous_month(date: NaiveDate) -> NaiveDate {
+    let last_month = date.with_day(1).expect("First of the month must exist") - Duration::days(1);
+    last_month
+        .with_day(1)
+        .expect("First of the month must exist")
+}
+
+/// Builds the attendance report of the month starting on `first`, from attendance records and
+/// current streaks, replacing any earlier snapshot of the same month. Members are included if
+/// they had attendance records during the month, which the daily task creates for active members.
+/// Streaks are taken as of `now`, not the month's end, so regenerating an old month records
+/// the streaks members have today.
+pub async fn generate_monthly_report(
+    first: NaiveDate,
+    now: NaiveDateTime,
+    rules: &ReportRules,
+    streak_rules: &StreakRules,
+    pool: &PgPool,
+) -> Result<MonthlyReport, sqlx::Error> {
+    let (year, month) = (first.year(), first.month() as i32);
+    let last = first
+        .checked_add_months(chrono::Months::new(1))
+        .expect("Next month must exist")
+        - Duration::days(1);
+    let working_days: Vec<NaiveDate> = first
+        .iter_days()
+        .take_while(|date| *date <= last)
+        .filter(|date| streak_rules.is_working_day(*date))
+        .collect();
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM MonthlyReport WHERE year = $1 AND month = $2")
+        .bind(year)
+        .bind(month)
+        .execute(&mut *tx)
+        .await?;
+
+    let report = sqlx::query_as::<_, MonthlyReport>(
+        "INSERT INTO MonthlyReport (year, month, working_days, late_after, generated_at)
+        VALUES ($1, $2, $3, $4, $5) RETURNING *",
+    )
+    .bind(year)
+    .bind(month)
+    .bind(working_days.len() as i32)
+    .bind(rules.late_after)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO MemberMonthlyReport (report_id, member_id, group_id, working_days, days_present,
+            attendance_percentage, average_hours, late_arrivals, current_streak, max_streak, missed_streak)
+        SELECT $1, m.member_id, m.group_id, a.working_days, a.days_present,
+            CASE WHEN a.working_days = 0 THEN 0
+                ELSE 100.0 * a.present_on_working_days / a.working_days END,
+            a.average_hours, a.late_arrivals,
+            COALESCE(s.current_streak, 0), COALESCE(s.max_streak, 0), COALESCE(s.missed_streak, 0)
+        FROM (
+            SELECT member_id,
+                COUNT(*) FILTER (WHERE date = ANY($4))::int AS working_days,
+                COUNT(*) FILTER (WHERE is_present)::int AS days_present,
+                COUNT(*) FILTER (WHERE is_present AND date = ANY($4)) AS present_on_working_days,
+                AVG(EXTRACT(EPOCH FROM (time_out - time_in)) / 3600)
+                    FILTER (WHERE is_present)::float8 AS average_hours,
+                COUNT(*) FILTER (WHERE is_present AND time_in > $5)::int AS late_arrivals
+            FROM Attendance
+            WHERE date BETWEEN $2 AND $3
+            GROUP BY member_id
+        ) a
+        JOIN Member m ON m.member_id = a.member_id
+        LEFT JOIN StatusUpdateStreak s ON s.member_id = a.member_id",
+    )
+    .bind(report.report_id)
+    .bind(first)
+    .bind(last)
+    .bind(&working_days)
+    .bind(rules.late_after)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO GroupMonthlyReport (report_id, group_id, members, days_present,
+            attendance_percentage, average_hours, late_arrivals, members_on_streak)
+        SELECT report_id, group_id, COUNT(*), SUM(days_present), AVG(attendance_percentage),
+            AVG(average_hours), SUM(late_arrivals), COUNT(*) FILTER (WHERE current_streak > 0)
+        FROM MemberMonthlyReport
+        WHERE report_id = $1
+        GROUP BY report_id, group_id",
+    )
+    .bind(report.report_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(report)
+}
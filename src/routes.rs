@@ -1,34 +1,96 @@
 use async_graphql::{http::GraphiQLSource, EmptySubscription, Schema};
-use async_graphql_axum::GraphQL;
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use std::sync::Arc;
+
 use axum::{
+    extract::State,
+    http::HeaderMap,
     response::{Html, IntoResponse},
     routing::get,
     Router,
 };
+use sqlx::PgPool;
 use tower_http::cors::CorsLayer;
 
-use crate::graphql::{Mutation, Query};
+use crate::{
+    auth::Authenticator,
+    contributions::{self, WebhookState},
+    email::{self, EmailState},
+    export::{self, ExportState},
+    graphql::{Mutation, Query},
+    health::{self, HealthState},
+    metrics::{self, MetricsState},
+    rate_limit::RateLimitLayer,
+};
+
+#[derive(Clone)]
+struct GraphQLState {
+    schema: Schema<Query, Mutation, EmptySubscription>,
+    authenticator: Authenticator,
+}
 
+#[allow(clippy::too_many_arguments)]
 pub fn setup_router(
     schema: Schema<Query, Mutation, EmptySubscription>,
+    authenticator: Authenticator,
+    pool: Arc<PgPool>,
     cors: CorsLayer,
+    rate_limit: RateLimitLayer,
+    webhook_state: WebhookState,
+    email_state: EmailState,
+    health_state: HealthState,
+    metrics_state: MetricsState,
     is_dev: bool,
 ) -> Router {
+    let export_state = ExportState {
+        pool,
+        authenticator: authenticator.clone(),
+    };
+    let state = GraphQLState {
+        schema,
+        authenticator,
+    };
+
     let router = Router::new()
-        .route_service("/", GraphQL::new(schema.clone()))
-        .layer(cors);
+        .route("/", get(graphql_handler).post(graphql_handler))
+        .with_state(state.clone())
+        .merge(export::router(export_state))
+        .layer(rate_limit)
+        .layer(cors)
+        // Webhooks come from forges rather than browsers, so they skip CORS and rate limiting.
+        .merge(contributions::router(webhook_state))
+        // Unsubscribe links are opened from email clients.
+        .merge(email::router(email_state))
+        // Probes and scrapers come from the infrastructure rather than browsers.
+        .merge(health::router(health_state))
+        .merge(metrics::router(metrics_state));
 
     if is_dev {
         tracing::info!("GraphiQL playground enabled at /graphiql");
-        router.route(
-            "/graphiql",
-            get(graphiql).post_service(GraphQL::new(schema)),
+        router.merge(
+            Router::new()
+                .route("/graphiql", get(graphiql).post(graphql_handler))
+                .with_state(state),
         )
     } else {
         router
     }
 }
 
+/// Runs a GraphQL request on behalf of whoever the `Authorization` header identifies.
+async fn graphql_handler(
+    State(state): State<GraphQLState>,
+    headers: HeaderMap,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    let viewer = state.authenticator.viewer(&headers);
+    state
+        .schema
+        .execute(request.into_inner().data(viewer))
+        .await
+        .into()
+}
+
 async fn graphiql() -> impl IntoResponse {
     Html(
         GraphiQLSource::build()
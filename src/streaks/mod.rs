@@ -0,0 +1,460 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use sqlx::PgPool;
+
+use crate::{
+    events::{emit, Event},
+    models::{
+        status_update::{RecordStatusUpdateInput, StatusUpdate},
+        status_update_streak::{DayStatus, StatusUpdateDay, StatusUpdateStreak, StreakRun},
+    },
+};
+
+/// Decides which status updates count towards a streak.
+#[derive(Clone)]
+pub struct StreakRules {
+    /// An update for a day must be posted before this time on the following day.
+    pub deadline: NaiveTime,
+    /// Days of the week on which no update is expected. Missing them doesn't break a streak.
+    pub excluded_days: Vec<Weekday>,
+}
+
+impl StreakRules {
+    pub fn new(deadline: NaiveTime, excluded_days: Vec<Weekday>) -> Result<Self, String> {
+        if excluded_days.len() >= 7 {
+            return Err("At least one day of the week must be a working day".to_string());
+        }
+        Ok(Self {
+            deadline,
+            excluded_days,
+        })
+    }
+
+    pub fn is_working_day(&self, date: NaiveDate) -> bool {
+        !self.excluded_days.contains(&date.weekday())
+    }
+
+    fn next_working_day(&self, date: NaiveDate) -> NaiveDate {
+        let mut next = date + Duration::days(1);
+        while !self.is_working_day(next) {
+            next += Duration::days(1);
+        }
+        next
+    }
+
+    /// The last moment an update for `date` can be posted and still count.
+    pub fn deadline_for(&self, date: NaiveDate) -> NaiveDateTime {
+        (date + Duration::days(1)).and_time(self.deadline)
+    }
+
+    fn counts(&self, date: NaiveDate, posted_at: NaiveDateTime) -> bool {
+        self.is_working_day(date) && posted_at < self.deadline_for(date)
+    }
+
+    /// Number of working days from `from` to `to`, both inclusive.
+    pub fn working_days_between(&self, from: NaiveDate, to: NaiveDate) -> i32 {
+        from.iter_days()
+            .take_while(|date| *date <= to)
+            .filter(|date| self.is_working_day(*date))
+            .count() as i32
+    }
+
+    /// The most recent working day whose deadline has already passed at `now`.
+    pub fn last_due_day(&self, now: NaiveDateTime) -> NaiveDate {
+        let mut day = now.date() - Duration::days(1);
+        if now < self.deadline_for(day) {
+            day -= Duration::days(1);
+        }
+        while !self.is_working_day(day) {
+            day -= Duration::days(1);
+        }
+        day
+    }
+}
+
+/// Current, longest and missed streak of a member at a point in time.
+pub struct StreakStats {
+    pub current_streak: i32,
+    pub max_streak: i32,
+    pub missed_streak: i32,
+}
+
+/// Groups `(date, posted_at)` pairs of a member's updates, sorted by date, into runs of
+/// consecutive working days. Updates posted after their deadline or on excluded days are ignored.
+pub fn streak_runs(updates: &[(NaiveDate, NaiveDateTime)], rules: &StreakRules) -> Vec<StreakRun> {
+    let mut runs: Vec<StreakRun> = Vec::new();
+
+    for &(date, posted_at) in updates {
+        if !rules.counts(date, posted_at) {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if rules.next_working_day(run.end) == date => {
+                run.end = date;
+                run.length += 1;
+            }
+            _ => runs.push(StreakRun {
+                start: date,
+                end: date,
+                length: 1,
+            }),
+        }
+    }
+
+    runs
+}
+
+/// Computes a member's streaks from their updates. The current streak is only alive if the
+/// last due working day was covered. Otherwise, the missed streak counts the working days
+/// since the last counted update, or since `joined_on` if there never was one.
+pub fn compute_streaks(
+    updates: &[(NaiveDate, NaiveDateTime)],
+    joined_on: NaiveDate,
+    now: NaiveDateTime,
+    rules: &StreakRules,
+) -> StreakStats {
+    let runs = streak_runs(updates, rules);
+    let due = rules.last_due_day(now);
+
+    let max_streak = runs.iter().map(|run| run.length).max().unwrap_or(0);
+    let (current_streak, missed_streak) = match runs.last() {
+        Some(run) if run.end >= due => (run.length, 0),
+        Some(run) => (
+            0,
+            rules.working_days_between(run.end + Duration::days(1), due),
+        ),
+        None => (0, rules.working_days_between(joined_on, due)),
+    };
+
+    StreakStats {
+        current_streak,
+        max_streak,
+        missed_streak,
+    }
+}
+
+/// Describes every day from `from` to `to`, both inclusive, based on a member's updates.
+/// Days before the member joined on `joined_on` are left out.
+pub fn calendar(
+    updates: &[(NaiveDate, NaiveDateTime)],
+    from: NaiveDate,
+    to: NaiveDate,
+    joined_on: NaiveDate,
+    now: NaiveDateTime,
+    rules: &StreakRules,
+) -> Vec<StatusUpdateDay> {
+    let updates: HashMap<NaiveDate, NaiveDateTime> = updates.iter().copied().collect();
+
+    from.max(joined_on)
+        .iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| {
+            let posted_at = updates.get(&date).copied();
+
+            let status = match posted_at {
+                _ if !rules.is_working_day(date) => DayStatus::Excluded,
+                Some(posted_at) if rules.counts(date, posted_at) => DayStatus::Updated,
+                Some(_) => DayStatus::Late,
+                None if now >= rules.deadline_for(date) => DayStatus::Missed,
+                None => DayStatus::Pending,
+            };
+
+            StatusUpdateDay {
+                date,
+                status,
+                posted_at,
+            }
+        })
+        .collect()
+}
+
+/// Stores a status update posted at `posted_at` unless the member already has one for that
+/// day, in which case the existing update is returned unchanged. Doesn't touch the member's
+/// streak, and ignores `input.posted_at`, which callers have to vet themselves.
+pub async fn record_status_update(
+    input: &RecordStatusUpdateInput,
+    posted_at: NaiveDateTime,
+    pool: &PgPool,
+) -> Result<StatusUpdate, sqlx::Error> {
+    let inserted = sqlx::query_as::<_, StatusUpdate>(
+        "INSERT INTO StatusUpdate (member_id, date, source_message_id, content, posted_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (member_id, date) DO NOTHING
+        RETURNING *",
+    )
+    .bind(input.member_id)
+    .bind(input.date)
+    .bind(&input.source_message_id)
+    .bind(&input.content)
+    .bind(posted_at)
+    .fetch_optional(pool)
+    .await?;
+
+    match inserted {
+        Some(status_update) => Ok(status_update),
+        None => {
+            sqlx::query_as::<_, StatusUpdate>(
+                "SELECT * FROM StatusUpdate WHERE member_id = $1 AND date = $2",
+            )
+            .bind(input.member_id)
+            .bind(input.date)
+            .fetch_one(pool)
+            .await
+        }
+    }
+}
+
+/// Fetches `(date, posted_at)` of every update a member recorded up to `until`, sorted by date.
+pub async fn fetch_updates(
+    member_id: i32,
+    until: NaiveDate,
+    pool: &PgPool,
+) -> Result<Vec<(NaiveDate, NaiveDateTime)>, sqlx::Error> {
+    sqlx::query_as::<_, (NaiveDate, NaiveDateTime)>(
+        "SELECT date, posted_at FROM StatusUpdate WHERE member_id = $1 AND date <= $2 ORDER BY date",
+    )
+    .bind(member_id)
+    .bind(until)
+    .fetch_all(pool)
+    .await
+}
+
+/// Fetches `(date, posted_at)` of every update a member recorded from `from` to `to`,
+/// both inclusive, sorted by date.
+pub async fn fetch_updates_between(
+    member_id: i32,
+    from: NaiveDate,
+    to: NaiveDate,
+    pool: &PgPool,
+) -> Result<Vec<(NaiveDate, NaiveDateTime)>, sqlx::Error> {
+    sqlx::query_as::<_, (NaiveDate, NaiveDateTime)>(
+        "SELECT date, posted_at FROM StatusUpdate
+        WHERE member_id = $1 AND date BETWEEN $2 AND $3 ORDER BY date",
+    )
+    .bind(member_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+}
+
+/// The day a member joined, from which updates are expected.
+pub async fn joined_on(member_id: i32, pool: &PgPool) -> Result<NaiveDate, sqlx::Error> {
+    sqlx::query_scalar::<_, NaiveDate>(
+        "SELECT COALESCE(created_at, CURRENT_TIMESTAMP)::date FROM Member WHERE member_id = $1",
+    )
+    .bind(member_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Recalculates a member's `StatusUpdateStreak` from their recorded status updates.
+/// `max_streak` never decreases, so streaks counted before updates were recorded are kept.
+/// Emits `streak.broken` if the member's current streak went back to zero.
+pub async fn refresh_streak(
+    member_id: i32,
+    now: NaiveDateTime,
+    rules: &StreakRules,
+    pool: &PgPool,
+) -> Result<StatusUpdateStreak, sqlx::Error> {
+    let joined_on = joined_on(member_id, pool).await?;
+    let updates = fetch_updates(member_id, now.date(), pool).await?;
+
+    let stats = compute_streaks(&updates, joined_on, now, rules);
+
+    let mut tx = pool.begin().await?;
+    let previous_streak = sqlx::query_scalar::<_, i32>(
+        "SELECT current_streak FROM StatusUpdateStreak WHERE member_id = $1 FOR UPDATE",
+    )
+    .bind(member_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .unwrap_or(0);
+
+    let streak = sqlx::query_as::<_, StatusUpdateStreak>(
+        "
+        INSERT INTO StatusUpdateStreak (member_id, current_streak, max_streak, missed_streak)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (member_id) DO UPDATE SET
+            current_streak = EXCLUDED.current_streak,
+            max_streak = GREATEST(StatusUpdateStreak.max_streak, EXCLUDED.max_streak),
+            missed_streak = EXCLUDED.missed_streak
+        RETURNING *",
+    )
+    .bind(member_id)
+    .bind(stats.current_streak)
+    .bind(stats.max_streak)
+    .bind(stats.missed_streak)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if previous_streak > 0 && streak.current_streak == 0 {
+        let event = Event::StreakBroken {
+            broken_streak: previous_streak,
+            streak: &streak,
+        };
+        emit(event, &mut *tx).await?;
+    }
+
+    tx.commit().await?;
+    Ok(streak)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> StreakRules {
+        StreakRules::new(
+            NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+            vec![Weekday::Sun],
+        )
+        .unwrap()
+    }
+
+    /// A day in October 2026. The 11th and 18th are Sundays.
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    fn at(date: u32, hour: u32) -> NaiveDateTime {
+        day(date).and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    /// Updates posted on the evening of their own day.
+    fn on_time(days: &[u32]) -> Vec<(NaiveDate, NaiveDateTime)> {
+        days.iter().map(|&d| (day(d), at(d, 20))).collect()
+    }
+
+    #[test]
+    fn excluded_days_dont_break_streaks() {
+        let stats = compute_streaks(&on_time(&[9, 10, 12, 13, 14]), day(1), at(15, 11), &rules());
+
+        assert_eq!(stats.current_streak, 5);
+        assert_eq!(stats.max_streak, 5);
+        assert_eq!(stats.missed_streak, 0);
+    }
+
+    #[test]
+    fn late_updates_break_streaks() {
+        let mut updates = on_time(&[8, 9, 10, 12, 13]);
+        updates.push((day(14), at(15, 11)));
+        updates.extend(on_time(&[15, 16, 17]));
+
+        let stats = compute_streaks(&updates, day(1), at(19, 11), &rules());
+
+        assert_eq!(stats.current_streak, 3);
+        assert_eq!(stats.max_streak, 5);
+        assert_eq!(stats.missed_streak, 0);
+    }
+
+    #[test]
+    fn last_due_day_waits_for_the_deadline_and_skips_excluded_days() {
+        let rules = rules();
+
+        assert_eq!(rules.last_due_day(at(14, 11)), day(13));
+        assert_eq!(rules.last_due_day(at(14, 9)), day(12));
+        // Monday's update isn't due yet, and Sunday's never is.
+        assert_eq!(rules.last_due_day(at(13, 9)), day(10));
+        assert_eq!(rules.last_due_day(at(19, 11)), day(17));
+    }
+
+    #[test]
+    fn streaks_stay_alive_until_the_deadline() {
+        let rules = rules();
+        let updates = on_time(&[12]);
+
+        let before = compute_streaks(&updates, day(12), at(14, 9), &rules);
+        assert_eq!(before.current_streak, 1);
+
+        let after = compute_streaks(&updates, day(12), at(14, 11), &rules);
+        assert_eq!(after.current_streak, 0);
+        assert_eq!(after.max_streak, 1);
+    }
+
+    #[test]
+    fn rejects_rules_without_working_days() {
+        let deadline = NaiveTime::from_hms_opt(10, 0, 0).unwrap();
+        let every_day = vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ];
+
+        assert!(StreakRules::new(deadline, every_day).is_err());
+    }
+
+    #[test]
+    fn groups_updates_into_runs() {
+        let mut updates = on_time(&[8, 9, 10, 12, 14, 15]);
+        updates.insert(4, (day(13), at(14, 11)));
+
+        let runs = streak_runs(&updates, &rules());
+
+        assert_eq!(
+            runs,
+            [
+                StreakRun {
+                    start: day(8),
+                    end: day(12),
+                    length: 4,
+                },
+                StreakRun {
+                    start: day(14),
+                    end: day(15),
+                    length: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_missed_working_days() {
+        let rules = rules();
+
+        let missed = compute_streaks(&on_time(&[12, 13]), day(1), at(19, 11), &rules);
+        assert_eq!(missed.missed_streak, 4);
+
+        // Without any update, days are missed since joining.
+        let never = compute_streaks(&[], day(15), at(19, 11), &rules);
+        assert_eq!(never.current_streak, 0);
+        assert_eq!(never.missed_streak, 3);
+    }
+
+    #[test]
+    fn describes_every_calendar_day() {
+        let mut updates = on_time(&[16]);
+        updates.push((day(17), at(18, 11)));
+
+        let statuses: Vec<DayStatus> =
+            calendar(&updates, day(15), day(19), day(1), at(19, 9), &rules())
+                .into_iter()
+                .map(|day| day.status)
+                .collect();
+
+        assert_eq!(
+            statuses,
+            [
+                DayStatus::Missed,
+                DayStatus::Updated,
+                DayStatus::Late,
+                DayStatus::Excluded,
+                DayStatus::Pending,
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_starts_when_the_member_joined() {
+        let days = calendar(&[], day(15), day(19), day(17), at(19, 9), &rules());
+
+        let dates: Vec<NaiveDate> = days.iter().map(|day| day.date).collect();
+        assert_eq!(dates, [day(17), day(18), day(19)]);
+        assert!(calendar(&[], day(15), day(19), day(20), at(19, 9), &rules()).is_empty());
+    }
+}
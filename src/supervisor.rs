@@ -0,0 +1,293 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use chrono::{Local, NaiveDateTime};
+use serde::Serialize;
+use tokio::{
+    sync::watch,
+    task::{AbortHandle, JoinHandle},
+};
+use tracing::{error, info, warn};
+
+use crate::{config::timezone, metrics::Metrics};
+
+/// Wait before restarting a crashed task, doubled after every crash in a row.
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// A task that ran this long before crashing is restarted after `MIN_RESTART_DELAY` again.
+const STABLE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Asks background tasks to stop. Cloning shares the request.
+#[derive(Clone)]
+pub struct Shutdown(Arc<watch::Sender<bool>>);
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+}
+
+impl Shutdown {
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Completes once shutdown is requested, or right away if it already has been.
+    pub async fn requested(&self) {
+        let mut receiver = self.0.subscribe();
+        // The sender lives as long as `self`, so waiting can't fail.
+        let _ = receiver.wait_for(|requested| *requested).await;
+    }
+}
+
+/// Completes when the process is asked to stop with SIGTERM, as Docker does, or Ctrl+C.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Ctrl+C handler must be installed.");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("SIGTERM handler must be installed.")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// What each supervised task has been doing, shared with `/ready`.
+#[derive(Clone, Default)]
+pub struct TaskStatuses(Arc<Mutex<BTreeMap<&'static str, TaskState>>>);
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskState {
+    pub running: bool,
+    pub restarts: u32,
+    pub last_crashed_at: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+}
+
+impl TaskStatuses {
+    fn update(&self, task: &'static str, update: impl FnOnce(&mut TaskState)) {
+        // The lock is never held across anything that could panic, so the state is still valid.
+        let mut tasks = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        update(tasks.entry(task).or_default());
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<&'static str, TaskState> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Runs background tasks, restarting them if they crash until shutdown is requested.
+pub struct Supervisor {
+    shutdown: Shutdown,
+    statuses: TaskStatuses,
+    metrics: Metrics,
+    tasks: Vec<SupervisedTask>,
+}
+
+/// The supervising loop of a task, and the run of the task it's currently waiting for.
+struct SupervisedTask {
+    handle: JoinHandle<()>,
+    current_run: Arc<Mutex<Option<AbortHandle>>>,
+}
+
+impl SupervisedTask {
+    fn abort(&self) {
+        if let Some(run) = &*self
+            .current_run
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+        {
+            run.abort();
+        }
+        self.handle.abort();
+    }
+}
+
+impl Supervisor {
+    pub fn new(shutdown: Shutdown, metrics: Metrics) -> Self {
+        Self {
+            shutdown,
+            statuses: TaskStatuses::default(),
+            metrics,
+            tasks: Vec::new(),
+        }
+    }
+
+    pub fn statuses(&self) -> TaskStatuses {
+        self.statuses.clone()
+    }
+
+    /// Runs the future `task` returns in the background. It's started again with a growing
+    /// delay if it panics, or returns before shutdown is requested.
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, task: F)
+    where
+        F: Fn(Shutdown) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let shutdown = self.shutdown.clone();
+        let statuses = self.statuses.clone();
+        let metrics = self.metrics.clone();
+        let current_run = Arc::new(Mutex::new(None));
+        let run_slot = current_run.clone();
+        statuses.update(name, |state| state.running = true);
+
+        let handle = tokio::spawn(async move {
+            let mut delay = MIN_RESTART_DELAY;
+            loop {
+                statuses.update(name, |state| state.running = true);
+                let started = Instant::now();
+                let run = tokio::spawn(task(shutdown.clone()));
+                *run_slot.lock().unwrap_or_else(PoisonError::into_inner) = Some(run.abort_handle());
+                let result = run.await;
+                statuses.update(name, |state| state.running = false);
+                if shutdown.is_requested() {
+                    break;
+                }
+
+                let problem = match result {
+                    Ok(()) => "Stopped unexpectedly".to_string(),
+                    Err(e) if e.is_panic() => panic_message(e.into_panic()),
+                    Err(e) => e.to_string(),
+                };
+                if started.elapsed() >= STABLE_AFTER {
+                    delay = MIN_RESTART_DELAY;
+                }
+                error!(
+                    "Background task {} crashed: {}. Restarting in {}s",
+                    name,
+                    problem,
+                    delay.as_secs()
+                );
+                let now = Local::now().with_timezone(&timezone()).naive_local();
+                statuses.update(name, |state| {
+                    state.restarts += 1;
+                    state.last_crashed_at = Some(now);
+                    state.last_error = Some(problem);
+                });
+                metrics.record_task_restart(name);
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = shutdown.requested() => break,
+                }
+                delay = (delay * 2).min(MAX_RESTART_DELAY);
+            }
+            info!("Background task {} stopped", name);
+        });
+        self.tasks.push(SupervisedTask {
+            handle,
+            current_run,
+        });
+    }
+
+    /// Waits for every task to stop after shutdown was requested, until `deadline`, then
+    /// aborts the ones still running. Returns whether they all stopped in time.
+    pub async fn wait_until(mut self, deadline: tokio::time::Instant) -> bool {
+        let mut stopped = true;
+        for task in &mut self.tasks {
+            if tokio::time::timeout_at(deadline, &mut task.handle)
+                .await
+                .is_err()
+            {
+                stopped = false;
+                break;
+            }
+        }
+        if !stopped {
+            warn!("Background tasks didn't stop in time, aborting them");
+            for task in &self.tasks {
+                task.abort();
+            }
+        }
+        stopped
+    }
+}
+
+/// Panics carry a `String` or a `&str`, depending on how they were raised.
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "Panicked".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    /// Set when dropped, which happens once its task is aborted.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn aborts_tasks_ignoring_shutdown_after_deadline() {
+        let shutdown = Shutdown::default();
+        let mut supervisor = Supervisor::new(shutdown.clone(), Metrics::new());
+        let dropped = Arc::new(AtomicBool::new(false));
+        let flag = dropped.clone();
+        supervisor.spawn("stubborn", move |_| {
+            let flag = DropFlag(flag.clone());
+            async move {
+                let _flag = flag;
+                std::future::pending::<()>().await;
+            }
+        });
+        tokio::task::yield_now().await;
+
+        shutdown.trigger();
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(50);
+        assert!(!supervisor.wait_until(deadline).await);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn waits_for_tasks_that_stop() {
+        let shutdown = Shutdown::default();
+        let mut supervisor = Supervisor::new(shutdown.clone(), Metrics::new());
+        supervisor.spawn(
+            "polite",
+            |shutdown| async move { shutdown.requested().await },
+        );
+        tokio::task::yield_now().await;
+
+        shutdown.trigger();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        assert!(supervisor.wait_until(deadline).await);
+    }
+}
@@ -0,0 +1,340 @@
+use async_graphql::{value, Error, ErrorExtensions, MaybeUndefined, Value};
+use regex::Regex;
+
+use crate::models::{member::CreateMemberInput, profile::UpdateProfileInput};
+
+/// Used when `ROOT_ROLL_NO_PATTERN` is not set. Matches roll numbers such as `AM.EN.U4CSE21001`.
+pub const DEFAULT_ROLL_NO_PATTERN: &str = r"^[A-Z]{2}\.[A-Z]{2}\.[A-Z0-9]{3,6}[0-9]{5}$";
+
+/// Rules that depend on deployment configuration, kept in the GraphQL context.
+pub struct ValidationRules {
+    roll_no: Regex,
+    email: Regex,
+    discord_id: Regex,
+    handle: Regex,
+}
+
+impl ValidationRules {
+    pub fn new(roll_no_pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            roll_no: Regex::new(roll_no_pattern)?,
+            email: Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$")
+                .expect("Hardcoded regex must be valid"),
+            // Discord snowflakes are 64-bit integers, currently 17 to 20 digits long.
+            discord_id: Regex::new(r"^[0-9]{17,20}$").expect("Hardcoded regex must be valid"),
+            // Covers GitHub, GitLab and LinkedIn usernames.
+            handle: Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._-]{0,99}$")
+                .expect("Hardcoded regex must be valid"),
+        })
+    }
+}
+
+/// A single invalid field, named the way it appears in the GraphQL schema.
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Every field error found in one input. Converted into a single GraphQL error
+/// so that clients can show all problems at once instead of one per request.
+#[derive(Default)]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl ValidationErrors {
+    fn add(&mut self, field: &'static str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field,
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<ValidationErrors> for Error {
+    fn from(errors: ValidationErrors) -> Self {
+        let fields: Vec<Value> = errors
+            .0
+            .iter()
+            .map(|e| value!({ "field": e.field, "message": e.message.as_str() }))
+            .collect();
+
+        Error::new("Input validation failed").extend_with(|_, ext| {
+            ext.set("code", "VALIDATION_FAILED");
+            ext.set("fields", Value::List(fields));
+        })
+    }
+}
+
+/// Converts `aa-bb-cc-dd-ee-ff` or `aabb.ccdd.eeff` style addresses into `AA:BB:CC:DD:EE:FF`.
+/// Returns `None` if the input doesn't contain exactly 12 hex digits.
+pub fn normalize_mac_address(mac: &str) -> Option<String> {
+    let digits: String = mac
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digits = digits.to_ascii_uppercase();
+    let octets: Vec<&str> = (0..12).step_by(2).map(|i| &digits[i..i + 2]).collect();
+    Some(octets.join(":"))
+}
+
+impl CreateMemberInput {
+    /// Checks every field and normalizes the ones that have a canonical form.
+    /// All failures are collected rather than returning at the first one.
+    pub fn validate(mut self, rules: &ValidationRules) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+
+        self.roll_no = self.roll_no.trim().to_ascii_uppercase();
+        if !rules.roll_no.is_match(&self.roll_no) {
+            errors.add("rollNo", "Roll number does not match the expected format");
+        }
+
+        if self.name.trim().is_empty() {
+            errors.add("name", "Name cannot be empty");
+        }
+
+        self.email = self.email.trim().to_string();
+        if !rules.email.is_match(&self.email) {
+            errors.add("email", "Not a valid email address");
+        }
+
+        if !(1..=4).contains(&self.year) {
+            errors.add("year", "Year must be between 1 and 4");
+        }
+
+        match normalize_mac_address(&self.mac_address) {
+            Some(mac) => self.mac_address = mac,
+            None => errors.add(
+                "macAddress",
+                "MAC address must contain 12 hex digits, e.g. AA:BB:CC:DD:EE:FF",
+            ),
+        }
+
+        self.discord_id = self.discord_id.trim().to_string();
+        if !rules.discord_id.is_match(&self.discord_id) {
+            errors.add("discordId", "Discord ID must be a numeric snowflake");
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+const MAX_BIO_LENGTH: usize = 1000;
+const MAX_PRONOUNS_LENGTH: usize = 32;
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LENGTH: usize = 40;
+
+impl UpdateProfileInput {
+    /// Checks the fields that are being set. Handles are stored without a leading `@`,
+    /// and blank or repeated skills and interests are dropped.
+    pub fn validate(mut self, rules: &ValidationRules) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+
+        if let MaybeUndefined::Value(bio) = &mut self.bio {
+            *bio = bio.trim().to_string();
+            if bio.chars().count() > MAX_BIO_LENGTH {
+                errors.add(
+                    "bio",
+                    format!("Bio can be at most {} characters", MAX_BIO_LENGTH),
+                );
+            }
+        }
+
+        if let MaybeUndefined::Value(pronouns) = &mut self.pronouns {
+            *pronouns = pronouns.trim().to_string();
+            if pronouns.chars().count() > MAX_PRONOUNS_LENGTH {
+                errors.add(
+                    "pronouns",
+                    format!("Pronouns can be at most {} characters", MAX_PRONOUNS_LENGTH),
+                );
+            }
+        }
+
+        for (field, handle) in [
+            ("githubHandle", &mut self.github_handle),
+            ("gitlabHandle", &mut self.gitlab_handle),
+            ("linkedinHandle", &mut self.linkedin_handle),
+        ] {
+            if let MaybeUndefined::Value(handle) = handle {
+                *handle = handle.trim().trim_start_matches('@').to_string();
+                if !rules.handle.is_match(handle) {
+                    errors.add(field, "Not a valid username");
+                }
+            }
+        }
+
+        for (field, tags) in [
+            ("skills", &mut self.skills),
+            ("interests", &mut self.interests),
+        ] {
+            if let Some(tags) = tags {
+                let mut cleaned: Vec<String> = Vec::new();
+                for tag in tags.iter().map(|tag| tag.trim()) {
+                    if !tag.is_empty() && !cleaned.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                        cleaned.push(tag.to_string());
+                    }
+                }
+
+                if cleaned.len() > MAX_TAGS {
+                    errors.add(field, format!("At most {} entries are allowed", MAX_TAGS));
+                }
+                if cleaned
+                    .iter()
+                    .any(|tag| tag.chars().count() > MAX_TAG_LENGTH)
+                {
+                    errors.add(
+                        field,
+                        format!("Entries can be at most {} characters", MAX_TAG_LENGTH),
+                    );
+                }
+                *tags = cleaned;
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::member::Sex;
+
+    use super::*;
+
+    fn rules() -> ValidationRules {
+        ValidationRules::new(DEFAULT_ROLL_NO_PATTERN).unwrap()
+    }
+
+    fn member() -> CreateMemberInput {
+        CreateMemberInput {
+            roll_no: " am.en.u4cse21001 ".to_string(),
+            name: "Ananya".to_string(),
+            email: " ananya@amfoss.in ".to_string(),
+            sex: Sex::F,
+            year: 2,
+            hostel: "Saraswati".to_string(),
+            mac_address: "aa-bb-cc-dd-ee-ff".to_string(),
+            discord_id: "123456789012345678".to_string(),
+            group_id: 1,
+        }
+    }
+
+    fn fields(errors: &ValidationErrors) -> Vec<&'static str> {
+        errors.0.iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn normalizes_mac_addresses() {
+        for mac in ["aa:bb:cc:dd:ee:ff", "AA-BB-CC-DD-EE-FF", "aabb.ccdd.eeff"] {
+            assert_eq!(
+                normalize_mac_address(mac).as_deref(),
+                Some("AA:BB:CC:DD:EE:FF")
+            );
+        }
+        for mac in [
+            "",
+            "aa:bb:cc:dd:ee",
+            "aa:bb:cc:dd:ee:ff:00",
+            "gg:bb:cc:dd:ee:ff",
+        ] {
+            assert_eq!(normalize_mac_address(mac), None);
+        }
+    }
+
+    #[test]
+    fn accepts_and_normalizes_a_valid_member() {
+        let member = member().validate(&rules()).ok().unwrap();
+
+        assert_eq!(member.roll_no, "AM.EN.U4CSE21001");
+        assert_eq!(member.email, "ananya@amfoss.in");
+        assert_eq!(member.mac_address, "AA:BB:CC:DD:EE:FF");
+    }
+
+    #[test]
+    fn reports_every_invalid_member_field() {
+        let input = CreateMemberInput {
+            roll_no: "21001".to_string(),
+            name: " ".to_string(),
+            email: "ananya".to_string(),
+            year: 5,
+            mac_address: "aa:bb".to_string(),
+            discord_id: "ananya#1234".to_string(),
+            ..member()
+        };
+
+        let errors = input.validate(&rules()).err().unwrap();
+
+        assert_eq!(
+            fields(&errors),
+            ["rollNo", "name", "email", "year", "macAddress", "discordId"]
+        );
+    }
+
+    #[test]
+    fn uses_the_configured_roll_no_pattern() {
+        let rules = ValidationRules::new(r"^B[0-9]{6}$").unwrap();
+        let input = CreateMemberInput {
+            roll_no: "b210001".to_string(),
+            ..member()
+        };
+
+        assert_eq!(input.validate(&rules).ok().unwrap().roll_no, "B210001");
+        assert!(member().validate(&rules).is_err());
+        assert!(ValidationRules::new("[").is_err());
+    }
+
+    #[test]
+    fn cleans_profile_fields() {
+        let input = UpdateProfileInput {
+            bio: MaybeUndefined::Value("  Rustacean ".to_string()),
+            github_handle: MaybeUndefined::Value("@ananya".to_string()),
+            linkedin_handle: MaybeUndefined::Null,
+            skills: Some(vec![
+                "Rust".to_string(),
+                " rust ".to_string(),
+                "".to_string(),
+                "Go".to_string(),
+            ]),
+            ..UpdateProfileInput::default()
+        };
+
+        let profile = input.validate(&rules()).ok().unwrap();
+
+        assert_eq!(profile.bio, MaybeUndefined::Value("Rustacean".to_string()));
+        assert_eq!(
+            profile.github_handle,
+            MaybeUndefined::Value("ananya".to_string())
+        );
+        assert_eq!(profile.linkedin_handle, MaybeUndefined::Null);
+        assert_eq!(profile.skills.unwrap(), ["Rust", "Go"]);
+    }
+
+    #[test]
+    fn reports_every_invalid_profile_field() {
+        let input = UpdateProfileInput {
+            bio: MaybeUndefined::Value("a".repeat(MAX_BIO_LENGTH + 1)),
+            gitlab_handle: MaybeUndefined::Value("not a handle".to_string()),
+            interests: Some((0..=MAX_TAGS).map(|i| i.to_string()).collect()),
+            ..UpdateProfileInput::default()
+        };
+
+        let errors = input.validate(&rules()).err().unwrap();
+
+        assert_eq!(fields(&errors), ["bio", "gitlabHandle", "interests"]);
+    }
+}